@@ -0,0 +1,271 @@
+extern crate num;
+use self::num::{Float, NumCast};
+use super::*;
+use std::error::Error;
+use std::fmt;
+
+///Stage coefficients of the Dormand-Prince 5(4) tableau; the nodes are not needed since forces do not depend on time.
+const STAGES: [[f64; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+
+///Weights of the fifth order solution.
+const FIFTH: [f64; 7] = [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0, 0.0];
+
+///Weights of the embedded fourth order solution.
+const FOURTH: [f64; 7] = [5179.0 / 57600.0, 0.0, 7571.0 / 16695.0, 393.0 / 640.0, -92097.0 / 339200.0,
+    187.0 / 2100.0, 1.0 / 40.0];
+
+///The error returned when a step within tolerance would need to be smaller than the minimum step.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MinimumStepError<D> {
+    ///The minimum step, which was attempted and rejected.
+    pub dt: D,
+    ///The error of that step relative to the tolerance; a value above one is out of tolerance.
+    pub error: D,
+}
+
+impl<D> fmt::Display for MinimumStepError<D>
+    where D: fmt::Display
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "step error {} times the tolerance at the minimum step size {}", self.error, self.dt)
+    }
+}
+
+impl<D> Error for MinimumStepError<D>
+    where D: fmt::Display + fmt::Debug
+{
+}
+
+///An adaptive stepper using the embedded Dormand-Prince 5(4) Runge-Kutta pair.
+///
+///Every step is taken with the fifth order solution and its difference from the fourth order solution estimates the
+///error. A step is accepted when, for every particle, the position and velocity error are both within
+///absolute_tolerance + relative_tolerance times the size of the position or velocity. The next step size is then
+///chosen from the error of the last attempt, always staying between min_dt and max_dt. Forces is called seven times
+///per attempted step.
+#[derive(Copy, Clone)]
+pub struct DormandPrince<D> {
+    pub absolute_tolerance: D,
+    pub relative_tolerance: D,
+    pub min_dt: D,
+    pub max_dt: D,
+    ///The step size that will be attempted next.
+    pub dt: D,
+}
+
+impl<D> DormandPrince<D>
+    where D: Float
+{
+    ///Create a stepper that starts by attempting the maximum step size.
+    pub fn new(absolute_tolerance: D, relative_tolerance: D, min_dt: D, max_dt: D) -> Self {
+        DormandPrince{
+            absolute_tolerance: absolute_tolerance,
+            relative_tolerance: relative_tolerance,
+            min_dt: min_dt,
+            max_dt: max_dt,
+            dt: max_dt,
+        }
+    }
+
+    ///Take one step within tolerance, returning the size of the step taken.
+    ///
+    ///If the error is out of tolerance at the minimum step size, the particles are left as they were and an error is
+    ///returned.
+    pub fn step<V, P, F>(&mut self, particles: &mut [P], mut forces: F) -> Result<D, MinimumStepError<D>>
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P]), V: Vector<D>
+    {
+        self.step_limited(particles, D::infinity(), &mut forces)
+    }
+
+    ///Advance the particles by exactly duration using as many steps as needed, returning the amount of steps.
+    pub fn advance<V, P, F>(&mut self, particles: &mut [P], duration: D, mut forces: F)
+        -> Result<usize, MinimumStepError<D>>
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P]), V: Vector<D>
+    {
+        let mut remaining = duration;
+        let mut steps = 0;
+        while remaining > D::zero() {
+            remaining = remaining - self.step_limited(particles, remaining, &mut forces)?;
+            steps += 1;
+        }
+        Ok(steps)
+    }
+
+    fn step_limited<V, P, F>(&mut self, particles: &mut [P], limit: D, forces: &mut F)
+        -> Result<D, MinimumStepError<D>>
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P]), V: Vector<D>
+    {
+        let initial: Vec<ParticleState<V>> = particles.iter().map(|p| p.state()).collect();
+        loop {
+            let dt = self.dt.max(self.min_dt).min(self.max_dt).min(limit);
+            let error = self.attempt(particles, &initial, dt, forces);
+            //Choose the next step from the error, which shrinks with the fifth power of the step. An error that isn't
+            //finite, such as from a NaN force, drops straight to the minimum step so that it fails there if it must.
+            let factor = if error == D::zero() {
+                constant(5.0)
+            } else if error.is_finite() {
+                (constant::<D>(0.9) * error.powf(constant(-0.2))).max(constant(0.2)).min(constant(5.0))
+            } else {
+                D::zero()
+            };
+            if error <= D::one() {
+                //A step cut short by the limit should not shrink the steps that follow it.
+                let next = if dt < self.dt {
+                    self.dt.max(dt * factor)
+                } else {
+                    dt * factor
+                };
+                self.dt = next.max(self.min_dt).min(self.max_dt);
+                return Ok(dt);
+            }
+            for (particle, state) in particles.iter_mut().zip(initial.iter()) {
+                particle.restore(state);
+            }
+            if dt <= self.min_dt {
+                self.dt = self.min_dt;
+                return Err(MinimumStepError{
+                    dt: dt,
+                    error: error,
+                });
+            }
+            self.dt = (dt * factor).max(self.min_dt);
+        }
+    }
+
+    ///Move the particles to the fifth order solution after dt and return the error relative to the tolerance.
+    fn attempt<V, P, F>(&self, particles: &mut [P], initial: &[ParticleState<V>], dt: D, forces: &mut F) -> D
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P]), V: Vector<D>
+    {
+        //The velocity and acceleration of every particle at each stage.
+        let mut velocities: Vec<Vec<V>> = Vec::with_capacity(7);
+        let mut accelerations: Vec<Vec<V>> = Vec::with_capacity(7);
+        for (stage, weights) in STAGES.iter().enumerate() {
+            if stage != 0 {
+                for (index, (particle, state)) in particles.iter_mut().zip(initial.iter()).enumerate() {
+                    let (position, velocity) = combine(weights, &velocities, &accelerations, index, dt);
+                    particle.restore(&ParticleState{
+                        position: state.position + position,
+                        velocity: state.velocity + velocity,
+                    });
+                }
+            }
+            let stage_forces = compute_forces(particles, forces);
+            velocities.push(particles.iter().map(|p| p.velocity()).collect());
+            accelerations.push(particles.iter().zip(stage_forces.iter()).map(|(p, &f)| f / p.inertia()).collect());
+        }
+        let mut error = D::zero();
+        for (index, (particle, state)) in particles.iter_mut().zip(initial.iter()).enumerate() {
+            let (position, velocity) = combine(&FIFTH, &velocities, &accelerations, index, dt);
+            let (lower_position, lower_velocity) = combine(&FOURTH, &velocities, &accelerations, index, dt);
+            let next = ParticleState{
+                position: state.position + position,
+                velocity: state.velocity + velocity,
+            };
+            let scale = |before: V, after: V| {
+                self.absolute_tolerance + self.relative_tolerance * before.displacement().max(after.displacement())
+            };
+            //Float::max ignores NaN, so it is kept explicitly to reject steps that produced it.
+            for term in &[(position - lower_position).displacement() / scale(state.position, next.position),
+                (velocity - lower_velocity).displacement() / scale(state.velocity, next.velocity)] {
+                if term.is_nan() || *term > error {
+                    error = *term;
+                }
+            }
+            particle.restore(&next);
+        }
+        error
+    }
+}
+
+///Combine the stage derivatives of one particle with Runge-Kutta weights into a change in position and velocity.
+fn combine<V, D>(weights: &[f64], velocities: &[Vec<V>], accelerations: &[Vec<V>], index: usize, dt: D) -> (V, V)
+    where V: Vector<D>, D: Float
+{
+    let mut position = V::zero();
+    let mut velocity = V::zero();
+    for (stage, &weight) in weights.iter().enumerate().take(velocities.len()) {
+        if weight != 0.0 {
+            let weight = constant::<D>(weight) * dt;
+            position = position + velocities[stage][index] * weight;
+            velocity = velocity + accelerations[stage][index] * weight;
+        }
+    }
+    (position, velocity)
+}
+
+fn constant<D>(value: f64) -> D
+    where D: Float
+{
+    <D as NumCast>::from(value).unwrap()
+}
+
+#[test]
+fn dormand_prince_close_encounter_test() {
+    //A nearly radial orbit that passes very close to the central body.
+    let mut particles = kepler_orbit();
+    particles[0].velocity = Cartesian2::new(0.0, -1e-4);
+    particles[1].velocity = Cartesian2::new(0.0, 0.1);
+    let initial = kepler_energy(&particles);
+    let mut stepper = DormandPrince::new(1e-10, 1e-10, 1e-9, 0.1);
+    let (mut smallest, mut largest) = (1.0f64, 0.0f64);
+    let mut time = 0.0;
+    while time < 5.0 {
+        let dt = stepper.step(&mut particles, kepler_forces).unwrap();
+        smallest = smallest.min(dt);
+        largest = largest.max(dt);
+        time += dt;
+    }
+    assert!(((kepler_energy(&particles) - initial) / initial).abs() < 1e-6);
+    assert!(largest <= 0.1);
+    assert!(largest / smallest > 100.0, "steps ranged from {} to {}", smallest, largest);
+}
+
+#[test]
+fn dormand_prince_advance_test() {
+    let mut particles = kepler_orbit();
+    let mut stepper = DormandPrince::new(1e-9, 1e-9, 1e-6, 0.5);
+    let steps = stepper.advance(&mut particles, 3.0, kepler_forces).unwrap();
+    assert!(steps > 6);
+    //Compare against a much finer fixed step solution.
+    let mut reference = kepler_orbit();
+    for _ in 0..3000 {
+        RungeKutta4.step(&mut reference, 0.001, kepler_forces);
+    }
+    assert!((particles[1].position - reference[1].position).displacement() < 1e-6);
+}
+
+#[test]
+fn dormand_prince_minimum_step_test() {
+    let mut particles = kepler_orbit();
+    let mut stepper = DormandPrince::new(1e-14, 0.0, 0.5, 1.0);
+    let error = stepper.step(&mut particles, kepler_forces).unwrap_err();
+    assert_eq!(error.dt, 0.5);
+    assert!(error.error > 1.0);
+    //The particles must be left untouched.
+    assert_eq!(particles[1].position.x, 1.0);
+    assert_eq!(particles[1].position.y, 0.0);
+}
+
+#[test]
+fn dormand_prince_nan_test() {
+    let mut particles = kepler_orbit();
+    let mut stepper = DormandPrince::new(1e-9, 1e-9, 1e-6, 0.5);
+    let mut calls = 0;
+    let error = stepper.step(&mut particles, |particles: &[BasicParticle<Cartesian2<f64>, f64>]| {
+        calls += 1;
+        particles[1].impulse(&Cartesian2::new(f64::NAN, 0.0));
+    }).unwrap_err();
+    assert_eq!(error.dt, 1e-6);
+    assert!(error.error.is_nan());
+    //One attempt at the first step and one at the minimum step, with seven force evaluations each.
+    assert_eq!(calls, 14);
+    assert_eq!(particles[1].position.x, 1.0);
+}
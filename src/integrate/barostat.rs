@@ -0,0 +1,407 @@
+extern crate num;
+use self::num::{Float, NumCast};
+use super::*;
+
+///The virial of the pair forces in a system, kept separately along each axis.
+///
+///The forces callback should start from a new Virial each time it is called and add every pair force it applies, so
+///that after a step the virial matches the forces at the final positions. Forces on single particles, such as drag,
+///do not contribute to the pressure and should not be added.
+#[derive(Copy, Clone, Debug)]
+pub struct Virial<V> {
+    diagonal: V,
+}
+
+impl<V> Virial<V> {
+    pub fn new<D>() -> Self
+        where V: Vector<D>, D: Float
+    {
+        Virial{
+            diagonal: V::zero(),
+        }
+    }
+
+    ///Add the force of a pair, where delta points from the first particle to the second and force acts on the second.
+    ///
+    ///With a periodic box, delta must be the wrapped delta that the force was computed from.
+    pub fn add<D>(&mut self, delta: V, force: V)
+        where V: CartesianVector<D>, D: Float
+    {
+        for axis in 0..V::dimensions() {
+            let component = self.diagonal.component(axis) + delta.component(axis) * force.component(axis);
+            self.diagonal.set_component(axis, component);
+        }
+    }
+
+    ///Same as add, but delta is measured from the position of the first particle to the second with a metric.
+    pub fn add_in<D, M: ?Sized>(&mut self, from: V, to: V, force: V, metric: &M)
+        where V: CartesianVector<D>, D: Float, M: Metric<V>
+    {
+        self.add(metric.delta(from, to), force);
+    }
+
+    ///The virial along each axis.
+    pub fn diagonal(&self) -> V
+        where V: Copy
+    {
+        self.diagonal
+    }
+
+    ///The sum of the virial over every axis.
+    pub fn trace<D>(&self) -> D
+        where V: CartesianVector<D>, D: Float
+    {
+        (0..V::dimensions()).fold(D::zero(), |total, axis| total + self.diagonal.component(axis))
+    }
+}
+
+///The amount of space inside a box, whose edges are twice its offset.
+pub fn volume<V, D>(bounds: &Box<V>) -> D
+    where V: CartesianVector<D>, D: Float
+{
+    (0..V::dimensions())
+        .fold(D::one(), |total, axis| total * (D::one() + D::one()) * bounds.offset.component(axis).abs())
+}
+
+///The pressure along each axis of the particles in a periodic box from their kinetic energy and the virial.
+pub fn pressure_tensor<V, D, P>(particles: &[P], bounds: &Box<V>, virial: &Virial<V>) -> V
+    where P: Velocity<V> + Inertia<D>, V: CartesianVector<D>, D: Float
+{
+    let mut tensor = virial.diagonal;
+    for particle in particles {
+        let velocity = particle.velocity();
+        for axis in 0..V::dimensions() {
+            let component = tensor.component(axis) + particle.inertia() * velocity.component(axis).powi(2);
+            tensor.set_component(axis, component);
+        }
+    }
+    tensor / volume(bounds)
+}
+
+///The pressure of the particles in a periodic box, which is the mean of the pressure_tensor over every axis.
+pub fn pressure<V, D, P>(particles: &[P], bounds: &Box<V>, virial: &Virial<V>) -> D
+    where P: Velocity<V> + Inertia<D>, V: CartesianVector<D>, D: Float
+{
+    let tensor = pressure_tensor(particles, bounds, virial);
+    (0..V::dimensions()).fold(D::zero(), |total, axis| total + tensor.component(axis)) /
+        <D as NumCast>::from(V::dimensions()).unwrap()
+}
+
+///Same as pressure_tensor, but the box is the periodic box of a metric.
+///
+///Panics if the metric has no periodic box, since the particles then have no volume.
+pub fn pressure_tensor_in<V, D, P, M: ?Sized>(particles: &[P], virial: &Virial<V>, metric: &M) -> V
+    where P: Velocity<V> + Inertia<D>, M: Metric<V>, V: CartesianVector<D>, D: Float
+{
+    pressure_tensor(particles, metric.bounds().expect("the pressure needs a metric with a periodic box"), virial)
+}
+
+///Same as pressure, but the box is the periodic box of a metric.
+///
+///Panics if the metric has no periodic box, since the particles then have no volume.
+pub fn pressure_in<V, D, P, M: ?Sized>(particles: &[P], virial: &Virial<V>, metric: &M) -> D
+    where P: Velocity<V> + Inertia<D>, M: Metric<V>, V: CartesianVector<D>, D: Float
+{
+    pressure(particles, metric.bounds().expect("the pressure needs a metric with a periodic box"), virial)
+}
+
+///Scale a box about its origin by a factor along each axis, moving the particles along with the space they are in.
+pub fn scale_box<V, D, P>(particles: &mut [P], bounds: &mut Box<V>, factors: V)
+    where P: IntegrableParticle<V, D>, V: CartesianVector<D>, D: Float
+{
+    for axis in 0..V::dimensions() {
+        let offset = bounds.offset.component(axis) * factors.component(axis);
+        bounds.offset.set_component(axis, offset);
+    }
+    for particle in particles.iter_mut() {
+        let mut position = particle.position() - bounds.origin;
+        for axis in 0..V::dimensions() {
+            let component = position.component(axis) * factors.component(axis);
+            position.set_component(axis, component);
+        }
+        particle.set_position(bounds.origin + position);
+    }
+}
+
+///Scale the velocity of every particle by a factor along each axis.
+fn scale_velocities<V, D, P>(particles: &mut [P], factors: V)
+    where P: IntegrableParticle<V, D>, V: CartesianVector<D>, D: Float
+{
+    for particle in particles.iter_mut() {
+        let mut velocity = particle.velocity();
+        for axis in 0..V::dimensions() {
+            let component = velocity.component(axis) * factors.component(axis);
+            velocity.set_component(axis, component);
+        }
+        particle.set_velocity(velocity);
+    }
+}
+
+///Berendsen weak coupling, which relaxes the pressure exponentially towards the target pressure.
+///
+///The box is scaled equally along every axis. Like the Berendsen thermostat, it does not produce the correct
+///fluctuations, but is very stable for bringing a system to a pressure.
+pub struct BerendsenBarostat<D> {
+    pub pressure: D,
+    ///The time over which the difference from the target pressure decays.
+    pub coupling_time: D,
+    ///How much the volume changes relative to itself per unit of pressure, which need only be roughly right.
+    pub compressibility: D,
+}
+
+impl<D> BerendsenBarostat<D>
+    where D: Float
+{
+    pub fn new(pressure: D, coupling_time: D, compressibility: D) -> Self {
+        BerendsenBarostat{
+            pressure: pressure,
+            coupling_time: coupling_time,
+            compressibility: compressibility,
+        }
+    }
+
+    ///Scale the box and the particles in it towards the target pressure over an amount of time.
+    ///
+    ///The virial must be from the forces at the current positions of the particles, such as the virial found by the
+    ///forces callback during the last step. Positions are moved, so the integrator should be reset afterwards.
+    pub fn apply<V, P>(&self, particles: &mut [P], bounds: &mut Box<V>, virial: &Virial<V>, time: D)
+        where P: IntegrableParticle<V, D>, V: CartesianVector<D>
+    {
+        let current = pressure(particles, bounds, virial);
+        let change = D::one() - self.compressibility * time / self.coupling_time * (self.pressure - current);
+        let factor = change.max(D::zero()).powf(D::one() / <D as NumCast>::from(V::dimensions()).unwrap());
+        let mut factors = V::zero();
+        for axis in 0..V::dimensions() {
+            factors.set_component(axis, factor);
+        }
+        scale_box(particles, bounds, factors);
+    }
+}
+
+///Velocity Verlet with a Parrinello-Rahman barostat for a box that stays aligned with the axes.
+///
+///The logarithm of the length of the box along each axis is given a mass and moves under the difference between the
+///pressure along that axis and the target pressure, so each axis of the box can change independently. The particles
+///drift with the expansion of the box and their velocities are damped by it. Without a thermostat, energy plus the
+///energy of the barostat is conserved; for constant temperature, apply a thermostat around each step.
+///
+///The forces callback is also given the box and must return the virial of the forces it applies. The forces and
+///virial at the end of each step are kept for the start of the next.
+pub struct ParrinelloRahman<V, D> {
+    pub pressure: D,
+    ///The inertia of the box, which sets how slowly its size oscillates.
+    pub mass: D,
+    rates: V,
+    forces: Vec<V>,
+    virial: Virial<V>,
+}
+
+impl<V, D> ParrinelloRahman<V, D>
+    where V: CartesianVector<D>, D: Float
+{
+    pub fn new(pressure: D, mass: D) -> Self {
+        ParrinelloRahman{
+            pressure: pressure,
+            mass: mass,
+            rates: V::zero(),
+            forces: Vec::new(),
+            virial: Virial::new(),
+        }
+    }
+
+    ///The relative rate of change of the length of the box along each axis.
+    pub fn rates(&self) -> V {
+        self.rates
+    }
+
+    ///The work done against the target pressure plus the kinetic energy of the box.
+    pub fn energy(&self, bounds: &Box<V>) -> D {
+        self.pressure * volume(bounds) + self.mass * self.rates.displacement_squared() / (D::one() + D::one())
+    }
+
+    ///Forget the forces kept from the last step as Integrator::reset does, which is also needed if the box changes.
+    pub fn reset(&mut self) {
+        self.forces.clear();
+    }
+
+    ///Advance the particles and the box forward in time by dt.
+    pub fn step<P, F>(&mut self, particles: &mut [P], bounds: &mut Box<V>, dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P], &Box<V>) -> Virial<V>
+    {
+        if self.forces.len() != particles.len() {
+            self.compute(particles, bounds, &mut forces);
+        }
+        let half = dt / (D::one() + D::one());
+        self.accelerate(particles, bounds, half);
+        scale_velocities(particles, self.factors(-half));
+        kick(particles, &self.forces, half);
+        drift(particles, half);
+        let factors = self.factors(dt);
+        scale_box(particles, bounds, factors);
+        drift(particles, half);
+        self.compute(particles, bounds, &mut forces);
+        kick(particles, &self.forces, half);
+        scale_velocities(particles, self.factors(-half));
+        self.accelerate(particles, bounds, half);
+    }
+
+    fn compute<P, F>(&mut self, particles: &mut [P], bounds: &Box<V>, forces: &mut F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P], &Box<V>) -> Virial<V>
+    {
+        self.virial = forces(particles, bounds);
+        self.forces = particles.iter_mut().map(|particle| particle.take_force()).collect();
+    }
+
+    ///The factor the box grows by along each axis over an amount of time.
+    fn factors(&self, time: D) -> V {
+        let mut factors = V::zero();
+        for axis in 0..V::dimensions() {
+            factors.set_component(axis, (self.rates.component(axis) * time).exp());
+        }
+        factors
+    }
+
+    fn accelerate<P>(&mut self, particles: &[P], bounds: &Box<V>, time: D)
+        where P: IntegrableParticle<V, D>
+    {
+        let tensor = pressure_tensor(particles, bounds, &self.virial);
+        let volume = volume(bounds);
+        for axis in 0..V::dimensions() {
+            let rate = self.rates.component(axis) +
+                volume * (tensor.component(axis) - self.pressure) / self.mass * time;
+            self.rates.set_component(axis, rate);
+        }
+    }
+}
+
+#[cfg(test)]
+type FluidParticle = BasicParticle<Cartesian2<f64>, f64>;
+
+///A square lattice of particles in a periodic box with scattered initial velocities.
+#[cfg(test)]
+fn fluid() -> (Vec<FluidParticle>, Box<Cartesian2<f64>>) {
+    let particles = (0..64).map(|i| {
+        let (x, y) = ((i % 8) as f64 * 1.2 - 4.2, (i / 8) as f64 * 1.2 - 4.2);
+        let i = i as f64;
+        let velocity = Cartesian2::new((i * 0.618).fract() - 0.5, (i * 0.414).fract() - 0.5);
+        BasicParticle::new(1.0, Cartesian2::new(x, y), velocity * 2.0, 1.0)
+    }).collect();
+    (particles, Box::new(Cartesian2::new(0.0, 0.0), Cartesian2::new(4.8, 4.8)))
+}
+
+///Lennard-Jones potential between two particles cut off at 2.5 and shifted to zero there.
+#[cfg(test)]
+fn lennard_jones(r2: f64) -> f64 {
+    let inverse6 = 1.0 / (r2 * r2 * r2);
+    4.0 * inverse6 * (inverse6 - 1.0) - 4.0 * (2.5f64.powi(-12) - 2.5f64.powi(-6))
+}
+
+///Apply Lennard-Jones forces cut off at 2.5 and return their virial.
+#[cfg(test)]
+fn fluid_forces(particles: &[FluidParticle], bounds: &Box<Cartesian2<f64>>) -> Virial<Cartesian2<f64>> {
+    let mut virial = Virial::new();
+    for i in 0..particles.len() {
+        for j in i + 1..particles.len() {
+            let delta = bounds.wrap_delta(particles[j].position - particles[i].position);
+            let r2 = delta.displacement_squared();
+            if r2 < 6.25 {
+                let inverse6 = 1.0 / (r2 * r2 * r2);
+                let force = delta * (24.0 * inverse6 * (2.0 * inverse6 - 1.0) / r2);
+                particles[i].impulse(&-force);
+                particles[j].impulse(&force);
+                virial.add(delta, force);
+            }
+        }
+    }
+    virial
+}
+
+#[cfg(test)]
+fn fluid_energy(particles: &[FluidParticle], bounds: &Box<Cartesian2<f64>>) -> f64 {
+    let mut energy = kinetic_energy(particles);
+    for i in 0..particles.len() {
+        for j in i + 1..particles.len() {
+            let r2 = bounds.wrap_delta(particles[j].position - particles[i].position).displacement_squared();
+            if r2 < 6.25 {
+                energy += lennard_jones(r2);
+            }
+        }
+    }
+    energy
+}
+
+#[test]
+fn pressure_test() {
+    //An ideal gas has the pressure of its kinetic energy alone.
+    let particles = vec![
+        BasicParticle::new(1.0, Cartesian2::new(0.0, 0.0), Cartesian2::new(1.0, 0.0), 2.0),
+        BasicParticle::new(1.0, Cartesian2::new(0.0, 0.0), Cartesian2::new(0.0, 2.0), 1.0),
+    ];
+    let bounds = Box::new(Cartesian2::new(0.0, 0.0), Cartesian2::new(1.0, 0.5));
+    let mut virial = Virial::new();
+    assert_eq!(volume(&bounds), 2.0);
+    let tensor = pressure_tensor(&particles, &bounds, &virial);
+    assert_eq!((tensor.x, tensor.y), (1.0, 2.0));
+    assert_eq!(pressure(&particles, &bounds, &virial), 1.5);
+    //A repulsive pair pushes outwards.
+    virial.add(Cartesian2::new(1.0, 0.0), Cartesian2::new(3.0, 0.0));
+    assert_eq!(virial.trace::<f64>(), 3.0);
+    assert_eq!(pressure(&particles, &bounds, &virial), 2.25);
+    //Through the walls of the box, a pair 1.5 apart along x is only 0.5 apart.
+    let mut wrapped = Virial::new();
+    wrapped.add_in(Cartesian2::new(0.75, 0.0), Cartesian2::new(-0.75, 0.0), Cartesian2::new(3.0, 0.0), &bounds);
+    assert_eq!(wrapped.trace::<f64>(), 1.5);
+    assert_eq!(pressure_in(&particles, &wrapped, &bounds), 1.875);
+    let tensor = pressure_tensor_in(&particles, &wrapped, &bounds);
+    assert_eq!((tensor.x, tensor.y), (1.75, 2.0));
+}
+
+#[test]
+#[should_panic]
+fn pressure_in_unbounded_test() {
+    let particles = vec![BasicParticle::new(1.0, Cartesian2::new(0.0, 0.0), Cartesian2::new(1.0, 0.0), 1.0)];
+    pressure_in(&particles, &Virial::new(), &Euclidean);
+}
+
+#[test]
+fn berendsen_barostat_test() {
+    let (mut particles, mut bounds) = fluid();
+    let initial = volume(&bounds);
+    let mut integrator = Thermostatted::new(VelocityVerlet::new(), Berendsen::new(1.0, 0.1));
+    let barostat = BerendsenBarostat::new(1.0, 1.0, 0.5);
+    let mut virial = Virial::new();
+    let mut average = 0.0;
+    for step in 0..6000 {
+        integrator.step(&mut particles, 0.005, |p: &[FluidParticle]| virial = fluid_forces(p, &bounds));
+        barostat.apply(&mut particles, &mut bounds, &virial, 0.005);
+        integrator.reset();
+        for particle in &mut particles {
+            particle.position = bounds.wrap_position(particle.position);
+        }
+        if step >= 3000 {
+            average += pressure(&particles, &bounds, &virial) / 3000.0;
+        }
+    }
+    assert!((average - 1.0).abs() < 0.1, "average pressure {}", average);
+    assert!(volume(&bounds) != initial);
+}
+
+#[test]
+fn parrinello_rahman_test() {
+    let (mut particles, mut bounds) = fluid();
+    let mut barostat = ParrinelloRahman::new(1.0, 1000.0);
+    let initial = fluid_energy(&particles, &bounds) + barostat.energy(&bounds);
+    let mut worst: f64 = 0.0;
+    for _ in 0..4000 {
+        barostat.step(&mut particles, &mut bounds, 0.002, fluid_forces);
+        for particle in &mut particles {
+            particle.position = bounds.wrap_position(particle.position);
+        }
+        let conserved = fluid_energy(&particles, &bounds) + barostat.energy(&bounds);
+        worst = worst.max(((conserved - initial) / initial).abs());
+    }
+    assert!(worst < 2e-3, "conserved energy drifted by {}", worst);
+    //The box is free to change shape as well as size.
+    assert!((bounds.offset.x - 4.8).abs() > 1e-3);
+    assert!((bounds.offset.x - bounds.offset.y).abs() > 1e-6);
+}
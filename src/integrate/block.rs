@@ -0,0 +1,203 @@
+extern crate num;
+use self::num::{Float, NumCast};
+use super::*;
+
+///Hierarchical power-of-two block timesteps built on kick-drift-kick leapfrog.
+///
+///Every particle is given a level from its acceleration, and a particle at level n takes steps of dt / 2^n, where a
+///particle's ideal step is sqrt(2 accuracy / |a|). Each step of dt is divided into 2^max_level substeps. All
+///particles drift every substep, so particles that are not being kicked simply coast as Particle::advance would
+///without any net force, but only the particles at the end of one of their own steps are kicked with the new forces.
+///Every particle is synchronised again at the end of each step of dt.
+///
+///Forces is called every substep that some particle is active in, and the forces on inactive particles are
+///discarded. Each particle keeps the force and level from the close of its last step for the opening kick of its next.
+#[derive(Clone)]
+pub struct BlockTimesteps<V, D> {
+    max_level: usize,
+    ///The length scale used to pick the step of each particle from its acceleration.
+    pub accuracy: D,
+    forces: Vec<V>,
+    levels: Vec<usize>,
+}
+
+impl<V, D> BlockTimesteps<V, D>
+    where V: Vector<D>, D: Float
+{
+    ///Create block timesteps with a finest level of max_level, which must be less than the bits of a usize.
+    pub fn new(max_level: usize, accuracy: D) -> Self {
+        assert!(max_level < usize::BITS as usize, "max_level must be less than the bits of a usize");
+        BlockTimesteps{
+            max_level: max_level,
+            accuracy: accuracy,
+            forces: Vec::new(),
+            levels: Vec::new(),
+        }
+    }
+
+    ///The finest level any particle may be put on.
+    pub fn max_level(&self) -> usize {
+        self.max_level
+    }
+
+    ///Change the finest level, which must be less than the bits of a usize, and reset the levels to match it.
+    pub fn set_max_level(&mut self, max_level: usize) {
+        assert!(max_level < usize::BITS as usize, "max_level must be less than the bits of a usize");
+        self.max_level = max_level;
+        self.reset();
+    }
+
+    ///The level of every particle during the last step.
+    pub fn levels(&self) -> &[usize] {
+        &self.levels
+    }
+
+    ///Find the coarsest level whose steps are no longer than the ideal step for an acceleration.
+    fn level(&self, acceleration: V, dt: D) -> usize {
+        let acceleration = acceleration.displacement();
+        if !acceleration.is_normal() {
+            return 0;
+        }
+        let ideal = ((D::one() + D::one()) * self.accuracy / acceleration).sqrt();
+        let mut level = 0;
+        let mut step = dt;
+        while level < self.max_level && step > ideal {
+            level += 1;
+            step = step / (D::one() + D::one());
+        }
+        level
+    }
+}
+
+impl<V, D> Integrator<V, D> for BlockTimesteps<V, D>
+    where V: Vector<D>, D: Float
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        if self.forces.len() != particles.len() || self.levels.len() != particles.len() {
+            self.forces = compute_forces(particles, &mut forces);
+            self.levels = particles.iter().zip(self.forces.iter())
+                .map(|(particle, &force)| self.level(force / particle.inertia(), dt))
+                .collect();
+        }
+        let max_level = self.max_level;
+        let substeps = 1usize << max_level;
+        let fine = dt / <D as NumCast>::from(substeps).unwrap();
+        let half_step = |level: usize| fine * <D as NumCast>::from(1usize << (max_level - level)).unwrap() /
+            (D::one() + D::one());
+        for substep in 0..substeps {
+            //Open the step of every particle whose step starts now.
+            for ((particle, &force), &level) in particles.iter_mut().zip(self.forces.iter()).zip(self.levels.iter()) {
+                if substep % (1 << (max_level - level)) == 0 {
+                    let velocity = particle.velocity() + force / particle.inertia() * half_step(level);
+                    particle.set_velocity(velocity);
+                }
+            }
+            drift(particles, fine);
+            let time = substep + 1;
+            if self.levels.iter().all(|&level| time % (1 << (max_level - level)) != 0) {
+                continue;
+            }
+            //Close the step of every particle whose step ends now and pick its next level.
+            let next = compute_forces(particles, &mut forces);
+            for (index, particle) in particles.iter_mut().enumerate() {
+                let level = self.levels[index];
+                if time % (1 << (max_level - level)) != 0 {
+                    continue;
+                }
+                let velocity = particle.velocity() + next[index] / particle.inertia() * half_step(level);
+                particle.set_velocity(velocity);
+                self.forces[index] = next[index];
+                //A particle may always move to a finer level, but only to a coarser level that is also in sync.
+                let mut level = self.level(next[index] / particle.inertia(), dt);
+                while time % (1 << (max_level - level)) != 0 {
+                    level += 1;
+                }
+                self.levels[index] = level;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.forces.clear();
+        self.levels.clear();
+    }
+}
+
+#[test]
+fn block_timesteps_test() {
+    //A tight binary with a distant light companion.
+    let mut particles = vec![
+        BasicParticle::new(0.5, Cartesian2::new(-0.005, 0.0), Cartesian2::new(0.0, -5.0), 0.5),
+        BasicParticle::new(0.5, Cartesian2::new(0.005, 0.0), Cartesian2::new(0.0, 5.0), 0.5),
+        BasicParticle::new(1e-3, Cartesian2::new(10.0, 0.0), Cartesian2::new(0.0, 0.1f64.sqrt()), 1e-3),
+    ];
+    let mut reference = particles.clone();
+    let forces = |particles: &[KeplerParticle]| {
+        for i in 0..particles.len() {
+            for j in i + 1..particles.len() {
+                gravitate(&particles[i], &particles[j], 1.0);
+            }
+        }
+    };
+    let energy = |particles: &[KeplerParticle]| {
+        let mut energy: f64 = particles.iter().map(|p| 0.5 * p.inertia * p.velocity.displacement_squared()).sum();
+        for i in 0..particles.len() {
+            for j in i + 1..particles.len() {
+                energy -= particles[i].quanta * particles[j].quanta /
+                    (particles[j].position - particles[i].position).displacement();
+            }
+        }
+        energy
+    };
+    let initial = energy(&particles);
+    let mut block = BlockTimesteps::new(8, 5e-6);
+    for _ in 0..100 {
+        block.step(&mut particles, 0.01, forces);
+    }
+    assert_eq!(block.levels(), &[8, 8, 0]);
+    assert!(((energy(&particles) - initial) / initial).abs() < 1e-2);
+
+    //Everyone on the finest step should agree on the path of the companion.
+    let mut leapfrog = Leapfrog::new();
+    for _ in 0..100 * 256 {
+        leapfrog.step(&mut reference, 0.01 / 256.0, forces);
+    }
+    assert!((particles[2].position - reference[2].position).displacement() < 1e-3);
+}
+
+#[test]
+fn block_timesteps_coasting_test() {
+    //Without forces every particle stays on the coarsest level and moves as advance would move it.
+    let mut particles = vec![BasicParticle::new(1.0, Cartesian2::new(1.0, 2.0), Cartesian2::new(0.5, -0.5), 1.0)];
+    let mut advanced = particles.clone();
+    let mut block = BlockTimesteps::new(4, 1.0);
+    block.step(&mut particles, 0.25, |_: &[KeplerParticle]| {});
+    advanced[0].advance(0.25);
+    assert_eq!(block.levels(), &[0]);
+    assert!((particles[0].position - advanced[0].position).displacement() < 1e-12);
+}
+
+#[test]
+fn block_timesteps_max_level_test() {
+    let mut particles = vec![
+        BasicParticle::new(0.5, Cartesian2::new(-0.005, 0.0), Cartesian2::new(0.0, -5.0), 0.5),
+        BasicParticle::new(0.5, Cartesian2::new(0.005, 0.0), Cartesian2::new(0.0, 5.0), 0.5),
+    ];
+    let forces = |particles: &[KeplerParticle]| gravitate(&particles[0], &particles[1], 1.0);
+    let mut block = BlockTimesteps::new(8, 5e-6);
+    block.step(&mut particles, 0.01, forces);
+    assert_eq!(block.levels(), &[8, 8]);
+    //Lowering the finest level between steps puts the particles back on levels it allows.
+    block.set_max_level(3);
+    assert_eq!(block.max_level(), 3);
+    block.step(&mut particles, 0.01, forces);
+    assert_eq!(block.levels(), &[3, 3]);
+}
+
+#[test]
+#[should_panic]
+fn block_timesteps_max_level_bits_test() {
+    BlockTimesteps::<Cartesian2<f64>, f64>::new(usize::BITS as usize, 1.0);
+}
@@ -0,0 +1,104 @@
+extern crate num;
+#[cfg(test)]
+extern crate nalgebra as na;
+use self::num::Float;
+use super::*;
+
+///The Boris pusher for charged particles in electric and magnetic fields.
+///
+///Each step applies half of the electric kick, rotates the velocity around the magnetic field, applies the other half
+///of the electric kick, and then drifts. The rotation preserves speed exactly, so unlike applying lorentz_field as an
+///impulse, a particle in a pure magnetic field keeps its kinetic energy and gyroradius indefinitely.
+///
+///The field closure gives the electric and magnetic field at a position as (electric, magnetic). The Charge of a
+///particle is its charge and its inertia is its mass.
+pub struct Boris<F> {
+    field: F,
+}
+
+impl<F> Boris<F> {
+    pub fn new(field: F) -> Self {
+        Boris{
+            field: field,
+        }
+    }
+
+    ///Advance the particles forward in time by dt.
+    ///
+    ///Any other forces, such as collisions between the particles, are applied by forces and are split into the two
+    ///halves of the electric kick.
+    pub fn step<V, D, P, G>(&mut self, particles: &mut [P], dt: D, mut forces: G)
+        where P: IntegrableParticle<V, D> + Charge<D>, G: FnMut(&[P]), V: Vector<D> + CrossVector, D: Float,
+        F: FnMut(&V) -> (V, V)
+    {
+        let other = compute_forces(particles, &mut forces);
+        let half = dt / (D::one() + D::one());
+        for (particle, &force) in particles.iter_mut().zip(other.iter()) {
+            let (electric, magnetic) = (self.field)(&particle.position());
+            let charge_mass = particle.charge() / particle.inertia();
+            let kick = (electric * charge_mass + force / particle.inertia()) * half;
+            let minus = particle.velocity() + kick;
+            //Rotate by the angle the magnetic field turns the velocity through during dt.
+            let t = magnetic * charge_mass * half;
+            let s = t * ((D::one() + D::one()) / (D::one() + t.displacement_squared()));
+            let prime = minus + V::cross(&minus, &t);
+            let plus = minus + V::cross(&prime, &s);
+            let velocity = plus + kick;
+            particle.set_velocity(velocity);
+            let position = particle.position() + velocity * dt;
+            particle.set_position(position);
+        }
+    }
+}
+
+#[cfg(test)]
+fn circumcenter(a: Cartesian3<f64>, b: Cartesian3<f64>, c: Cartesian3<f64>) -> Cartesian3<f64> {
+    //Only valid for points in the xy plane.
+    let d = 2.0 * (a.x * (b.y - c.y) + b.x * (c.y - a.y) + c.x * (a.y - b.y));
+    let (a2, b2, c2) = (a.displacement_squared(), b.displacement_squared(), c.displacement_squared());
+    Cartesian3::new((a2 * (b.y - c.y) + b2 * (c.y - a.y) + c2 * (a.y - b.y)) / d,
+        (a2 * (c.x - b.x) + b2 * (a.x - c.x) + c2 * (b.x - a.x)) / d, 0.0)
+}
+
+#[test]
+fn boris_cyclotron_test() {
+    type P = BasicParticle<Cartesian3<f64>, f64>;
+    let mut particles = vec![P::new(1.0, Cartesian3::new(0.0, 0.0, 0.0), Cartesian3::new(1.0, 0.0, 0.0), 1.0)];
+    let mut boris = Boris::new(|_: &Cartesian3<f64>| (Cartesian3::new(0.0, 0.0, 0.0), Cartesian3::new(0.0, 0.0, 1.0)));
+    let none = |_: &[P]| {};
+    let mut positions = Vec::new();
+    for _ in 0..3 {
+        boris.step(&mut particles, 0.1, none);
+        positions.push(particles[0].position);
+    }
+    let center = circumcenter(positions[0], positions[1], positions[2]);
+    let radius = (positions[0] - center).displacement();
+    //The gyroradius of a unit charge and mass at unit speed in a unit field is one.
+    assert!((radius - 1.0).abs() < 1e-2);
+    for _ in 0..1_000_000 {
+        boris.step(&mut particles, 0.1, none);
+    }
+    assert!(((particles[0].position - center).displacement() - radius).abs() < 1e-6);
+    assert!((particles[0].velocity.displacement() - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn boris_drift_test() {
+    //Crossed fields make the guiding center drift at E x B / B^2 regardless of charge.
+    type P = BasicParticle<na::Vector3<f64>, f64>;
+    let mut particles = vec![
+        P::new(1.0, na::Vector3::new(0.0, 0.0, 0.0), na::Vector3::new(0.0, 0.0, 0.0), 1.0),
+        P::new(-2.0, na::Vector3::new(0.0, 0.0, 0.0), na::Vector3::new(0.0, 0.0, 0.0), 1.0),
+    ];
+    let field = (na::Vector3::new(0.0, 0.1, 0.0), na::Vector3::new(0.0, 0.0, 1.0));
+    let mut boris = Boris::new(|_: &na::Vector3<f64>| field);
+    //Integrate over whole gyration periods of both particles.
+    let steps = (2.0 * ::std::f64::consts::PI * 100.0 / 0.001).round() as usize;
+    for _ in 0..steps {
+        boris.step(&mut particles, 0.001, |_: &[P]| {});
+    }
+    let time = steps as f64 * 0.001;
+    for particle in &particles {
+        assert!((particle.position.x / time - 0.1).abs() < 1e-3);
+    }
+}
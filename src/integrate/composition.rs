@@ -0,0 +1,98 @@
+extern crate num;
+use self::num::Float;
+use super::*;
+
+///Coefficient used by both fourth order compositions: 1 / (2 - 2^(1/3)).
+fn triple_jump<D>() -> D
+    where D: Float
+{
+    let two = D::one() + D::one();
+    D::one() / (two - two.cbrt())
+}
+
+///Yoshida's fourth order symplectic scheme.
+///
+///Each step is three kick-drift-kick leapfrog steps of w1 dt, w0 dt, and w1 dt, where w1 = 1 / (2 - 2^(1/3)) and
+///w0 = 1 - 2 w1 is negative, so the middle step goes backwards in time. Adjacent kicks are merged, and the force
+///of the last kick is kept for the first kick of the next step, so forces is called three times per step.
+#[derive(Clone, Default)]
+pub struct Yoshida4<V> {
+    forces: Vec<V>,
+}
+
+impl<V> Yoshida4<V> {
+    pub fn new() -> Self {
+        Yoshida4{
+            forces: Vec::new(),
+        }
+    }
+}
+
+impl<V, D> Integrator<V, D> for Yoshida4<V>
+    where V: Vector<D>, D: Float
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        if self.forces.len() != particles.len() {
+            self.forces = compute_forces(particles, &mut forces);
+        }
+        let two = D::one() + D::one();
+        let w1 = triple_jump::<D>();
+        let w0 = D::one() - two * w1;
+        kick(particles, &self.forces, w1 / two * dt);
+        drift(particles, w1 * dt);
+        self.forces = compute_forces(particles, &mut forces);
+        kick(particles, &self.forces, (w1 + w0) / two * dt);
+        drift(particles, w0 * dt);
+        self.forces = compute_forces(particles, &mut forces);
+        kick(particles, &self.forces, (w0 + w1) / two * dt);
+        drift(particles, w1 * dt);
+        self.forces = compute_forces(particles, &mut forces);
+        kick(particles, &self.forces, w1 / two * dt);
+    }
+
+    fn reset(&mut self) {
+        self.forces.clear();
+    }
+}
+
+///The fourth order symplectic scheme of Forest and Ruth.
+///
+///Each step alternates four drifts and three kicks with coefficients derived from theta = 1 / (2 - 2^(1/3)),
+///starting and ending with a drift, so forces is called three times per step and nothing is kept between steps.
+#[derive(Copy, Clone, Default)]
+pub struct ForestRuth;
+
+impl<V, D> Integrator<V, D> for ForestRuth
+    where V: Vector<D>, D: Float
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        let two = D::one() + D::one();
+        let theta = triple_jump::<D>();
+        let drifts = [theta / two, (D::one() - theta) / two, (D::one() - theta) / two, theta / two];
+        let kicks = [theta, D::one() - two * theta, theta];
+        for (index, &fraction) in drifts.iter().enumerate() {
+            drift(particles, fraction * dt);
+            if let Some(&fraction) = kicks.get(index) {
+                let stage_forces = compute_forces(particles, &mut forces);
+                kick(particles, &stage_forces, fraction * dt);
+            }
+        }
+    }
+}
+
+#[test]
+fn fourth_order_kepler_test() {
+    //Halving the step of a fourth order scheme should reduce the error sixteen fold.
+    let coarse = kepler_energy_error(&mut Yoshida4::new(), 0.02, 5000);
+    let fine = kepler_energy_error(&mut Yoshida4::new(), 0.01, 10000);
+    assert!(fine < 1e-6, "yoshida energy error {}", fine);
+    assert!(coarse / fine > 10.0, "yoshida convergence {}", coarse / fine);
+    let coarse = kepler_energy_error(&mut ForestRuth, 0.02, 5000);
+    let fine = kepler_energy_error(&mut ForestRuth, 0.01, 10000);
+    assert!(fine < 1e-6, "forest-ruth energy error {}", fine);
+    assert!(coarse / fine > 10.0, "forest-ruth convergence {}", coarse / fine);
+}
@@ -0,0 +1,22 @@
+extern crate num;
+use self::num::Float;
+use super::*;
+
+///The first order symplectic (semi-implicit) Euler scheme used by Particle::advance.
+///
+///The velocity is updated from the force first and the position is then updated from the new velocity.
+#[derive(Copy, Clone, Default)]
+pub struct SymplecticEuler;
+
+impl<V, D> Integrator<V, D> for SymplecticEuler
+    where D: Float
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        forces(particles);
+        for particle in particles.iter_mut() {
+            particle.advance(dt);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+extern crate num;
+extern crate rand;
+use self::num::{Float, NumCast};
+use self::rand::Rng;
+use self::rand::distributions::normal::StandardNormal;
+use super::*;
+
+///Draw a vector with an independent standard normal component along each axis.
+fn gaussian<V, D, R>(rng: &mut R) -> V
+    where V: CartesianVector<D>, D: Float, R: Rng
+{
+    let mut vector = V::zero();
+    for axis in 0..V::dimensions() {
+        let StandardNormal(value) = rng.gen::<StandardNormal>();
+        vector.set_component(axis, <D as NumCast>::from(value).unwrap());
+    }
+    vector
+}
+
+///Langevin dynamics, which holds particles at a temperature with friction and random kicks.
+///
+///The friction on each particle is the drag force of PhysicsParticle::drag with the given magnitude, and the random
+///kicks have the variance required by the fluctuation-dissipation theorem for the temperature, which is measured in
+///units of energy (the Boltzmann constant is folded into it). Each step uses the BAOAB splitting: a half kick, a half
+///drift, the exact solution of the friction and noise over the whole step, a half drift, and a half kick. The force
+///of the closing kick is kept for the opening kick of the next step.
+pub struct Langevin<V, D, R> {
+    ///Magnitude of the drag on every particle.
+    pub drag: D,
+    pub temperature: D,
+    rng: R,
+    forces: Vec<V>,
+}
+
+impl<V, D, R> Langevin<V, D, R>
+    where V: CartesianVector<D>, D: Float, R: Rng
+{
+    ///Create Langevin dynamics that draws its kicks from rng, so a seeded rng gives reproducible runs.
+    pub fn new(drag: D, temperature: D, rng: R) -> Self {
+        Langevin{
+            drag: drag,
+            temperature: temperature,
+            rng: rng,
+            forces: Vec::new(),
+        }
+    }
+
+    ///Access the random number generator used for the kicks.
+    pub fn rng(&mut self) -> &mut R {
+        &mut self.rng
+    }
+}
+
+impl<V, D, R> Integrator<V, D> for Langevin<V, D, R>
+    where V: CartesianVector<D>, D: Float, R: Rng
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        if self.forces.len() != particles.len() {
+            self.forces = compute_forces(particles, &mut forces);
+        }
+        let half = dt / (D::one() + D::one());
+        kick(particles, &self.forces, half);
+        drift(particles, half);
+        for particle in particles.iter_mut() {
+            //The velocity decays towards zero while gaining exactly enough noise to stay at the temperature.
+            let decay = (-self.drag / particle.inertia() * dt).exp();
+            let spread = ((D::one() - decay * decay) * self.temperature / particle.inertia()).sqrt();
+            let velocity = particle.velocity() * decay + gaussian::<V, D, R>(&mut self.rng) * spread;
+            particle.set_velocity(velocity);
+        }
+        drift(particles, half);
+        self.forces = compute_forces(particles, &mut forces);
+        kick(particles, &self.forces, half);
+    }
+
+    fn reset(&mut self) {
+        self.forces.clear();
+    }
+}
+
+///Overdamped Brownian dynamics, where friction dominates inertia.
+///
+///Velocity is not integrated; each step moves a particle directly by its force divided by the drag magnitude plus a
+///random displacement giving a diffusion coefficient of temperature / drag. The velocity and inertia of the particles
+///are neither used nor changed. The temperature is measured in units of energy.
+pub struct Brownian<D, R> {
+    ///Magnitude of the drag on every particle.
+    pub drag: D,
+    pub temperature: D,
+    rng: R,
+}
+
+impl<D, R> Brownian<D, R>
+    where D: Float, R: Rng
+{
+    ///Create Brownian dynamics that draws its displacements from rng, so a seeded rng gives reproducible runs.
+    pub fn new(drag: D, temperature: D, rng: R) -> Self {
+        Brownian{
+            drag: drag,
+            temperature: temperature,
+            rng: rng,
+        }
+    }
+
+    ///Access the random number generator used for the displacements.
+    pub fn rng(&mut self) -> &mut R {
+        &mut self.rng
+    }
+}
+
+impl<V, D, R> Integrator<V, D> for Brownian<D, R>
+    where V: CartesianVector<D>, D: Float, R: Rng
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        let forces = compute_forces(particles, &mut forces);
+        let spread = ((D::one() + D::one()) * self.temperature / self.drag * dt).sqrt();
+        for (particle, &force) in particles.iter_mut().zip(forces.iter()) {
+            let position = particle.position() + force / self.drag * dt +
+                gaussian::<V, D, R>(&mut self.rng) * spread;
+            particle.set_position(position);
+        }
+    }
+}
+
+#[cfg(test)]
+fn free_particles() -> Vec<BasicParticle<Cartesian2<f64>, f64>> {
+    (0..2000).map(|_| BasicParticle::new(1.0, Cartesian2::new(0.0, 0.0), Cartesian2::new(0.0, 0.0), 2.0)).collect()
+}
+
+#[test]
+fn langevin_equipartition_test() {
+    use self::rand::{SeedableRng, XorShiftRng};
+    let mut particles = free_particles();
+    let mut langevin = Langevin::new(4.0, 1.5, XorShiftRng::from_seed([1, 2, 3, 4]));
+    let mut kinetic = 0.0;
+    for step in 0..400 {
+        langevin.step(&mut particles, 0.05, |_: &[BasicParticle<Cartesian2<f64>, f64>]| {});
+        if step >= 200 {
+            kinetic += particles.iter().map(|p| 0.5 * p.inertia * p.velocity.displacement_squared()).sum::<f64>();
+        }
+    }
+    //Each of the two degrees of freedom of every particle should hold half of the temperature.
+    let per_particle = kinetic / 200.0 / particles.len() as f64;
+    assert!((per_particle - 1.5).abs() < 0.05, "kinetic energy per particle {}", per_particle);
+}
+
+#[test]
+fn langevin_reproducible_test() {
+    use self::rand::{SeedableRng, XorShiftRng};
+    let run = || {
+        let mut particles = vec![BasicParticle::new(1.0, Cartesian2::new(1.0, 0.0), Cartesian2::new(0.0, 1.0), 1.0)];
+        let mut langevin = Langevin::new(0.5, 1.0, XorShiftRng::from_seed([5, 6, 7, 8]));
+        for _ in 0..100 {
+            langevin.step(&mut particles, 0.01, |p: &[BasicParticle<Cartesian2<f64>, f64>]| p[0].drag(0.1));
+        }
+        particles[0].position
+    };
+    let (a, b) = (run(), run());
+    assert_eq!((a.x, a.y), (b.x, b.y));
+}
+
+#[test]
+fn brownian_diffusion_test() {
+    use self::rand::{SeedableRng, XorShiftRng};
+    let mut particles = free_particles();
+    let mut brownian = Brownian::new(2.0, 0.5, XorShiftRng::from_seed([9, 10, 11, 12]));
+    for _ in 0..100 {
+        brownian.step(&mut particles, 0.01, |_: &[BasicParticle<Cartesian2<f64>, f64>]| {});
+    }
+    //The mean squared displacement in two dimensions is 4 D t with D = temperature / drag.
+    let msd = particles.iter().map(|p| p.position.displacement_squared()).sum::<f64>() / particles.len() as f64;
+    assert!((msd / (4.0 * 0.25 * 1.0) - 1.0).abs() < 0.1, "mean squared displacement {}", msd);
+    assert!(particles.iter().all(|p| p.velocity.x == 0.0 && p.velocity.y == 0.0));
+}
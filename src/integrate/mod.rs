@@ -0,0 +1,124 @@
+//!Contains integrators that advance collections of particles through time under a force callback
+
+pub mod euler;
+pub mod verlet;
+pub mod runge_kutta;
+pub mod composition;
+pub mod adaptive;
+pub mod block;
+pub mod boris;
+pub mod langevin;
+pub mod thermostat;
+pub mod barostat;
+pub use self::euler::*;
+pub use self::verlet::*;
+pub use self::runge_kutta::*;
+pub use self::composition::*;
+pub use self::adaptive::*;
+pub use self::block::*;
+pub use self::boris::*;
+pub use self::langevin::*;
+pub use self::thermostat::*;
+pub use self::barostat::*;
+
+extern crate num;
+use self::num::Float;
+use super::particle::*;
+use super::vector::*;
+
+///A scheme for advancing a collection of particles forward in time.
+///
+///The forces callback is given the particles and must apply the net force on each of them with impulse, such as by
+///calling gravitate on every pair. An integrator may call it any amount of times per step, with the particles at
+///intermediate positions.
+pub trait Integrator<V, D> {
+    ///Advance the particles forward in time by dt.
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P]);
+    ///Forget anything kept from the last step.
+    ///
+    ///Integrators may keep the forces from the end of one step for the start of the next instead of calling forces
+    ///again, so this must be called whenever the particles or the forces are changed between steps by anything other
+    ///than the integrator.
+    fn reset(&mut self) {}
+}
+
+///Call forces on the particles and take the resulting net force on each of them.
+pub fn compute_forces<V, D, P, F>(particles: &mut [P], forces: &mut F) -> Vec<V>
+    where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+{
+    forces(particles);
+    particles.iter_mut().map(|particle| particle.take_force()).collect()
+}
+
+///Change the velocity of every particle by the acceleration of its force over time.
+pub fn kick<V, D, P>(particles: &mut [P], forces: &[V], time: D)
+    where P: IntegrableParticle<V, D>, V: Vector<D>, D: Float
+{
+    for (particle, &force) in particles.iter_mut().zip(forces.iter()) {
+        let velocity = particle.velocity() + force / particle.inertia() * time;
+        particle.set_velocity(velocity);
+    }
+}
+
+///Move every particle along its velocity over time.
+pub fn drift<V, D, P>(particles: &mut [P], time: D)
+    where P: IntegrableParticle<V, D>, V: Vector<D>, D: Float
+{
+    for particle in particles.iter_mut() {
+        let position = particle.position() + particle.velocity() * time;
+        particle.set_position(position);
+    }
+}
+
+#[cfg(test)]
+pub type KeplerParticle = BasicParticle<Cartesian2<f64>, f64>;
+
+///Create an eccentric two body orbit with a period of roughly 15 time units under gravitate with a magnitude of 1.
+#[cfg(test)]
+pub fn kepler_orbit() -> Vec<KeplerParticle> {
+    vec![
+        BasicParticle::new(1.0, Cartesian2::new(0.0, 0.0), Cartesian2::new(0.0, -1.2e-3), 1.0),
+        BasicParticle::new(1e-3, Cartesian2::new(1.0, 0.0), Cartesian2::new(0.0, 1.2), 1e-3),
+    ]
+}
+
+#[cfg(test)]
+pub fn kepler_forces(particles: &[KeplerParticle]) {
+    gravitate(&particles[0], &particles[1], 1.0);
+}
+
+#[cfg(test)]
+pub fn kepler_energy(particles: &[KeplerParticle]) -> f64 {
+    let kinetic: f64 = particles.iter().map(|p| 0.5 * p.inertia * p.velocity.displacement_squared()).sum();
+    let distance = (particles[1].position - particles[0].position).displacement();
+    kinetic - particles[0].quanta * particles[1].quanta / distance
+}
+
+///Integrate the Kepler orbit for steps and return the largest relative energy error along the way.
+#[cfg(test)]
+pub fn kepler_energy_error<I>(integrator: &mut I, dt: f64, steps: usize) -> f64
+    where I: Integrator<Cartesian2<f64>, f64>
+{
+    let mut particles = kepler_orbit();
+    let initial = kepler_energy(&particles);
+    let mut error: f64 = 0.0;
+    for _ in 0..steps {
+        integrator.step(&mut particles, dt, kepler_forces);
+        error = error.max(((kepler_energy(&particles) - initial) / initial).abs());
+    }
+    error
+}
+
+#[test]
+fn kepler_energy_test() {
+    //Roughly 13 orbits.
+    let euler = kepler_energy_error(&mut SymplecticEuler, 0.01, 20000);
+    let verlet = kepler_energy_error(&mut VelocityVerlet::new(), 0.01, 20000);
+    let leapfrog = kepler_energy_error(&mut Leapfrog::new(), 0.01, 20000);
+    assert!(euler < 1e-2, "symplectic euler energy error {}", euler);
+    assert!(verlet < 1e-4, "velocity verlet energy error {}", verlet);
+    assert!(leapfrog < 1e-4, "leapfrog energy error {}", leapfrog);
+    //The error of second order schemes should not be growing over time.
+    assert!(kepler_energy_error(&mut VelocityVerlet::new(), 0.01, 40000) < 1e-4);
+}
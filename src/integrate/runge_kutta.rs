@@ -0,0 +1,65 @@
+extern crate num;
+use self::num::Float;
+use super::*;
+
+///The classic fourth order Runge-Kutta scheme.
+///
+///This evaluates forces four times per step at trial states and is not symplectic, so it suits dissipative systems,
+///such as those with drag, where the forces depend on velocity.
+#[derive(Copy, Clone, Default)]
+pub struct RungeKutta4;
+
+impl<V, D> Integrator<V, D> for RungeKutta4
+    where V: Vector<D>, D: Float
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        let two = D::one() + D::one();
+        let initial: Vec<ParticleState<V>> = particles.iter().map(|p| p.state()).collect();
+        //The derivative of position (velocity) and velocity (acceleration) at each stage.
+        let mut velocities: Vec<Vec<V>> = Vec::with_capacity(4);
+        let mut accelerations: Vec<Vec<V>> = Vec::with_capacity(4);
+        for &fraction in &[D::zero(), dt / two, dt / two, dt] {
+            if let (Some(velocity), Some(acceleration)) = (velocities.last(), accelerations.last()) {
+                let stage = initial.iter().zip(velocity.iter().zip(acceleration.iter()))
+                    .map(|(state, (&velocity, &acceleration))| ParticleState{
+                        position: state.position + velocity * fraction,
+                        velocity: state.velocity + acceleration * fraction,
+                    });
+                for (particle, state) in particles.iter_mut().zip(stage) {
+                    particle.restore(&state);
+                }
+            }
+            let stage_forces = compute_forces(particles, &mut forces);
+            velocities.push(particles.iter().map(|p| p.velocity()).collect());
+            accelerations.push(particles.iter().zip(stage_forces.iter()).map(|(p, &f)| f / p.inertia()).collect());
+        }
+        let sixth = dt / (two + two + two);
+        for (index, (particle, state)) in particles.iter_mut().zip(initial.iter()).enumerate() {
+            let weighted = |derivatives: &Vec<Vec<V>>| {
+                derivatives[0][index] + (derivatives[1][index] + derivatives[2][index]) * two + derivatives[3][index]
+            };
+            particle.restore(&ParticleState{
+                position: state.position + weighted(&velocities) * sixth,
+                velocity: state.velocity + weighted(&accelerations) * sixth,
+            });
+        }
+    }
+}
+
+#[test]
+fn runge_kutta_drag_test() {
+    //With only drag the velocity decays exponentially.
+    let mut particles = vec![BasicParticle::new(1.0, Cartesian2::new(0.0, 0.0), Cartesian2::new(1.0, 2.0), 2.0)];
+    let drag = |particles: &[BasicParticle<Cartesian2<f64>, f64>]| particles[0].drag(0.5);
+    for _ in 0..100 {
+        RungeKutta4.step(&mut particles, 0.1, drag);
+    }
+    //v(t) = v0 e^(-kt/m) and x(t) = v0 m / k (1 - e^(-kt/m)) where k = 0.5, m = 2.0 and t = 10.
+    let decay = (-2.5f64).exp();
+    let expected_velocity = Cartesian2::new(1.0, 2.0) * decay;
+    let expected_position = Cartesian2::new(1.0, 2.0) * 4.0 * (1.0 - decay);
+    assert!((particles[0].velocity - expected_velocity).displacement() < 1e-8);
+    assert!((particles[0].position - expected_position).displacement() < 1e-8);
+}
@@ -0,0 +1,425 @@
+extern crate num;
+extern crate rand;
+use self::num::{Float, NumCast};
+use self::rand::Rng;
+use self::rand::distributions::normal::StandardNormal;
+use super::*;
+
+///Total kinetic energy of the particles.
+pub fn kinetic_energy<V, D, P>(particles: &[P]) -> D
+    where P: Velocity<V> + Inertia<D>, V: Vector<D>, D: Float
+{
+    particles.iter().fold(D::zero(), |total, particle| {
+        total + particle.inertia() * particle.velocity().displacement_squared() / (D::one() + D::one())
+    })
+}
+
+///Amount of degrees of freedom of the particles, which is one for every axis of every particle.
+pub fn degrees_of_freedom<V, D, P>(particles: &[P]) -> D
+    where V: CartesianVector<D>, D: Float
+{
+    <D as NumCast>::from(particles.len() * V::dimensions()).unwrap()
+}
+
+///Temperature of the particles from equipartition of their kinetic energy, in units of energy.
+///
+///Returns zero if there are no particles.
+pub fn kinetic_temperature<V, D, P>(particles: &[P]) -> D
+    where P: Velocity<V> + Inertia<D>, V: CartesianVector<D>, D: Float
+{
+    if particles.is_empty() {
+        return D::zero();
+    }
+    (D::one() + D::one()) * kinetic_energy(particles) / degrees_of_freedom::<V, D, P>(particles)
+}
+
+///Scale the velocity of every particle.
+fn rescale<V, D, P>(particles: &mut [P], factor: D)
+    where P: IntegrableParticle<V, D>, V: Vector<D>, D: Float
+{
+    for particle in particles.iter_mut() {
+        let velocity = particle.velocity() * factor;
+        particle.set_velocity(velocity);
+    }
+}
+
+///A deterministic or stochastic control of the temperature of particles through their velocities.
+///
+///Temperatures are measured in units of energy (the Boltzmann constant is folded into them).
+pub trait Thermostat<V, D> {
+    ///Couple the particles to the thermostat over an amount of time.
+    fn apply<P>(&mut self, particles: &mut [P], time: D)
+        where P: IntegrableParticle<V, D>;
+    ///Energy held by the thermostat, which added to the energy of the particles is conserved by the dynamics.
+    fn energy(&self) -> D;
+}
+
+///Runs an integrator with a thermostat applied for half of each step before and after it.
+pub struct Thermostatted<I, T> {
+    pub integrator: I,
+    pub thermostat: T,
+}
+
+impl<I, T> Thermostatted<I, T> {
+    pub fn new(integrator: I, thermostat: T) -> Self {
+        Thermostatted{
+            integrator: integrator,
+            thermostat: thermostat,
+        }
+    }
+}
+
+impl<V, D, I, T> Integrator<V, D> for Thermostatted<I, T>
+    where I: Integrator<V, D>, T: Thermostat<V, D>, D: Float
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        let half = dt / (D::one() + D::one());
+        self.thermostat.apply(particles, half);
+        self.integrator.step(particles, dt, forces);
+        self.thermostat.apply(particles, half);
+    }
+
+    fn reset(&mut self) {
+        self.integrator.reset();
+    }
+}
+
+///Berendsen weak coupling, which relaxes the kinetic temperature exponentially towards the target temperature.
+///
+///It does not produce the canonical distribution, but is very stable for bringing a system to a temperature.
+pub struct Berendsen<D> {
+    pub temperature: D,
+    ///The time over which the difference from the target temperature decays.
+    pub coupling_time: D,
+    energy: D,
+}
+
+impl<D> Berendsen<D>
+    where D: Float
+{
+    pub fn new(temperature: D, coupling_time: D) -> Self {
+        Berendsen{
+            temperature: temperature,
+            coupling_time: coupling_time,
+            energy: D::zero(),
+        }
+    }
+}
+
+impl<V, D> Thermostat<V, D> for Berendsen<D>
+    where V: CartesianVector<D>, D: Float
+{
+    fn apply<P>(&mut self, particles: &mut [P], time: D)
+        where P: IntegrableParticle<V, D>
+    {
+        let kinetic = kinetic_energy(particles);
+        let current = kinetic_temperature(particles);
+        if !current.is_normal() {
+            return;
+        }
+        let factor = (D::one() + time / self.coupling_time * (self.temperature / current - D::one()))
+            .max(D::zero()).sqrt();
+        rescale(particles, factor);
+        self.energy = self.energy - kinetic * (factor * factor - D::one());
+    }
+
+    ///The total kinetic energy removed from the particles.
+    fn energy(&self) -> D {
+        self.energy
+    }
+}
+
+///Stochastic velocity rescaling of Bussi, Donadio and Parrinello.
+///
+///The kinetic energy relaxes towards the target temperature like Berendsen, but with noise that makes the particles
+///sample the canonical distribution.
+pub struct Bussi<D, R> {
+    pub temperature: D,
+    ///The time over which the difference from the target temperature decays.
+    pub coupling_time: D,
+    rng: R,
+    energy: D,
+}
+
+impl<D, R> Bussi<D, R>
+    where D: Float, R: Rng
+{
+    ///Create a thermostat that draws its noise from rng, so a seeded rng gives reproducible runs.
+    pub fn new(temperature: D, coupling_time: D, rng: R) -> Self {
+        Bussi{
+            temperature: temperature,
+            coupling_time: coupling_time,
+            rng: rng,
+            energy: D::zero(),
+        }
+    }
+
+    ///Access the random number generator used for the rescaling.
+    pub fn rng(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    fn normal(&mut self) -> D {
+        let StandardNormal(value) = self.rng.gen::<StandardNormal>();
+        <D as NumCast>::from(value).unwrap()
+    }
+}
+
+impl<V, D, R> Thermostat<V, D> for Bussi<D, R>
+    where V: CartesianVector<D>, D: Float, R: Rng
+{
+    fn apply<P>(&mut self, particles: &mut [P], time: D)
+        where P: IntegrableParticle<V, D>
+    {
+        let kinetic = kinetic_energy(particles);
+        if !kinetic.is_normal() {
+            return;
+        }
+        let freedom = particles.len() * V::dimensions();
+        let freedom_d = <D as NumCast>::from(freedom).unwrap();
+        let target = freedom_d * self.temperature / (D::one() + D::one());
+        let decay = (-time / self.coupling_time).exp();
+        let first = self.normal();
+        let mut rest = D::zero();
+        for _ in 1..freedom {
+            let value = self.normal();
+            rest = rest + value * value;
+        }
+        //The new kinetic energy is drawn from the exact solution of the stochastic relaxation.
+        let ratio = target / (freedom_d * kinetic);
+        let squared = decay + (D::one() - decay) * (rest + first * first) * ratio +
+            (D::one() + D::one()) * first * (decay * (D::one() - decay) * ratio).sqrt();
+        let mut factor = squared.max(D::zero()).sqrt();
+        if first + (decay / ((D::one() - decay) * ratio)).sqrt() < D::zero() {
+            factor = -factor;
+        }
+        rescale(particles, factor);
+        self.energy = self.energy - kinetic * (squared - D::one());
+    }
+
+    ///The total kinetic energy removed from the particles.
+    fn energy(&self) -> D {
+        self.energy
+    }
+}
+
+///A Nosé-Hoover chain, which couples the particles to a chain of extended-system variables.
+///
+///The dynamics are deterministic and sample the canonical distribution. The first variable of the chain scales the
+///velocities of the particles and every further variable thermostats the one before it. The masses of the variables
+///are chosen from the coupling time, which is roughly the period of the oscillation of the temperature. The chain
+///is propagated with the scheme of Martyna, Tuckerman and Klein.
+pub struct NoseHooverChain<D> {
+    pub temperature: D,
+    pub coupling_time: D,
+    positions: Vec<D>,
+    velocities: Vec<D>,
+    ///Degrees of freedom of the particles the last time the chain was applied.
+    freedom: D,
+}
+
+impl<D> NoseHooverChain<D>
+    where D: Float
+{
+    ///Create a chain with length variables starting at rest.
+    ///
+    ///Panics if length is zero.
+    pub fn new(temperature: D, coupling_time: D, length: usize) -> Self {
+        assert!(length != 0, "zoom::NoseHooverChain::new(): the chain needs at least one variable");
+        NoseHooverChain{
+            temperature: temperature,
+            coupling_time: coupling_time,
+            positions: vec![D::zero(); length],
+            velocities: vec![D::zero(); length],
+            freedom: D::zero(),
+        }
+    }
+
+    ///The positions of the variables of the chain.
+    pub fn positions(&self) -> &[D] {
+        &self.positions
+    }
+
+    ///The velocities of the variables of the chain.
+    pub fn velocities(&self) -> &[D] {
+        &self.velocities
+    }
+
+    fn mass(&self, index: usize) -> D {
+        let mass = self.temperature * self.coupling_time * self.coupling_time;
+        if index == 0 {
+            mass * self.freedom
+        } else {
+            mass
+        }
+    }
+
+    ///Force on a variable of the chain given twice the kinetic energy of the particles.
+    fn force(&self, index: usize, twice_kinetic: D) -> D {
+        if index == 0 {
+            (twice_kinetic - self.freedom * self.temperature) / self.mass(0)
+        } else {
+            let previous = self.velocities[index - 1];
+            (self.mass(index - 1) * previous * previous - self.temperature) / self.mass(index)
+        }
+    }
+
+    ///Kick a variable of the chain, damped by the velocity of the variable after it.
+    fn kick(&mut self, index: usize, twice_kinetic: D, time: D) {
+        let half = time / (D::one() + D::one());
+        let quarter = half / (D::one() + D::one());
+        let damping = match self.velocities.get(index + 1) {
+            Some(&next) => (-next * quarter).exp(),
+            None => D::one(),
+        };
+        self.velocities[index] = self.velocities[index] * damping;
+        self.velocities[index] = self.velocities[index] + self.force(index, twice_kinetic) * half;
+        self.velocities[index] = self.velocities[index] * damping;
+    }
+}
+
+impl<V, D> Thermostat<V, D> for NoseHooverChain<D>
+    where V: CartesianVector<D>, D: Float
+{
+    fn apply<P>(&mut self, particles: &mut [P], time: D)
+        where P: IntegrableParticle<V, D>
+    {
+        self.freedom = degrees_of_freedom::<V, D, P>(particles);
+        let mut twice_kinetic = (D::one() + D::one()) * kinetic_energy(particles);
+        if !self.freedom.is_normal() {
+            return;
+        }
+        for index in (0..self.velocities.len()).rev() {
+            self.kick(index, twice_kinetic, time);
+        }
+        let factor = (-self.velocities[0] * time).exp();
+        rescale(particles, factor);
+        twice_kinetic = twice_kinetic * factor * factor;
+        for (position, &velocity) in self.positions.iter_mut().zip(self.velocities.iter()) {
+            *position = *position + velocity * time;
+        }
+        for index in 0..self.velocities.len() {
+            self.kick(index, twice_kinetic, time);
+        }
+    }
+
+    ///The kinetic and potential energy of the variables of the chain.
+    fn energy(&self) -> D {
+        let two = D::one() + D::one();
+        self.velocities.iter().zip(self.positions.iter()).enumerate()
+            .fold(D::zero(), |total, (index, (&velocity, &position))| {
+                let freedom = if index == 0 { self.freedom } else { D::one() };
+                total + self.mass(index) * velocity * velocity / two + freedom * self.temperature * position
+            })
+    }
+}
+
+#[cfg(test)]
+type LatticeParticle = BasicParticle<Cartesian2<f64>, f64>;
+
+///A square lattice of particles joined to their neighbours by springs, with scattered initial velocities.
+#[cfg(test)]
+fn lattice() -> Vec<LatticeParticle> {
+    (0..64).map(|i| {
+        let (x, y) = ((i % 8) as f64, (i / 8) as f64);
+        let i = i as f64;
+        let velocity = Cartesian2::new((i * 0.618).fract() - 0.5, (i * 0.414).fract() - 0.5);
+        BasicParticle::new(1.0, Cartesian2::new(x, y), velocity, 1.0 + (i * 0.271).fract())
+    }).collect()
+}
+
+#[cfg(test)]
+fn lattice_bonds() -> Vec<(usize, usize)> {
+    let mut bonds = Vec::new();
+    for i in 0..64 {
+        if i % 8 != 7 {
+            bonds.push((i, i + 1));
+        }
+        if i < 56 {
+            bonds.push((i, i + 8));
+        }
+    }
+    bonds
+}
+
+#[cfg(test)]
+fn lattice_forces(particles: &[LatticeParticle]) {
+    for (i, j) in lattice_bonds() {
+        hooke_equilibrium(&particles[i], &particles[j], 1.0, 5.0);
+    }
+}
+
+#[cfg(test)]
+fn lattice_energy(particles: &[LatticeParticle]) -> f64 {
+    lattice_bonds().iter().fold(kinetic_energy(particles), |total, &(i, j)| {
+        let stretch = (particles[j].position - particles[i].position).displacement() - 1.0;
+        total + 2.5 * stretch * stretch
+    })
+}
+
+#[test]
+fn kinetic_temperature_test() {
+    let particles = vec![
+        BasicParticle::new(1.0, Cartesian2::new(0.0, 0.0), Cartesian2::new(1.0, 0.0), 2.0),
+        BasicParticle::new(1.0, Cartesian2::new(0.0, 0.0), Cartesian2::new(0.0, 2.0), 1.0),
+    ];
+    assert_eq!(kinetic_energy(&particles), 3.0);
+    assert_eq!(kinetic_temperature(&particles), 1.5);
+    assert_eq!(kinetic_temperature::<Cartesian2<f64>, f64, LatticeParticle>(&[]), 0.0);
+}
+
+#[test]
+fn berendsen_test() {
+    let mut particles = lattice();
+    let mut integrator = Thermostatted::new(VelocityVerlet::new(), Berendsen::new(0.5, 0.5));
+    for _ in 0..2000 {
+        integrator.step(&mut particles, 0.01, lattice_forces);
+    }
+    let mut average = 0.0;
+    for _ in 0..1000 {
+        integrator.step(&mut particles, 0.01, lattice_forces);
+        average += kinetic_temperature(&particles) / 1000.0;
+    }
+    assert!((average - 0.5).abs() < 0.02, "average temperature {}", average);
+}
+
+#[test]
+fn bussi_test() {
+    use self::rand::{SeedableRng, XorShiftRng};
+    let mut particles = lattice();
+    let thermostat = Bussi::new(0.5, 0.5, XorShiftRng::from_seed([1, 2, 3, 4]));
+    let mut integrator = Thermostatted::new(VelocityVerlet::new(), thermostat);
+    let initial = lattice_energy(&particles);
+    let mut average = 0.0;
+    for step in 0..6000 {
+        integrator.step(&mut particles, 0.005, lattice_forces);
+        if step >= 2000 {
+            average += kinetic_temperature(&particles) / 4000.0;
+        }
+    }
+    assert!((average - 0.5).abs() < 0.05, "average temperature {}", average);
+    let conserved = lattice_energy(&particles) + Thermostat::<Cartesian2<f64>, f64>::energy(&integrator.thermostat);
+    assert!(((conserved - initial) / initial).abs() < 1e-2, "conserved energy {} from {}", conserved, initial);
+}
+
+#[test]
+fn nose_hoover_chain_test() {
+    let mut particles = lattice();
+    let mut integrator = Thermostatted::new(VelocityVerlet::new(), NoseHooverChain::new(0.5, 0.5, 4));
+    let initial = lattice_energy(&particles);
+    let mut average = 0.0;
+    let mut worst: f64 = 0.0;
+    for step in 0..10000 {
+        integrator.step(&mut particles, 0.005, lattice_forces);
+        let conserved = lattice_energy(&particles) +
+            Thermostat::<Cartesian2<f64>, f64>::energy(&integrator.thermostat);
+        worst = worst.max(((conserved - initial) / initial).abs());
+        if step >= 2000 {
+            average += kinetic_temperature(&particles) / 8000.0;
+        }
+    }
+    assert!(worst < 1e-3, "conserved energy drifted by {}", worst);
+    assert!((average - 0.5).abs() < 0.05, "average temperature {}", average);
+}
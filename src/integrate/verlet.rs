@@ -0,0 +1,88 @@
+extern crate num;
+use self::num::Float;
+use super::*;
+
+///The second order velocity Verlet scheme.
+///
+///The force at the end of each step is kept for the start of the next, so forces is called once per step.
+#[derive(Clone, Default)]
+pub struct VelocityVerlet<V> {
+    forces: Vec<V>,
+}
+
+impl<V> VelocityVerlet<V> {
+    pub fn new() -> Self {
+        VelocityVerlet{
+            forces: Vec::new(),
+        }
+    }
+}
+
+impl<V, D> Integrator<V, D> for VelocityVerlet<V>
+    where V: Vector<D>, D: Float
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        if self.forces.len() != particles.len() {
+            self.forces = compute_forces(particles, &mut forces);
+        }
+        let half = dt / (D::one() + D::one());
+        //x(t + dt) = x(t) + v(t) dt + a(t) dt^2 / 2
+        for (particle, &force) in particles.iter_mut().zip(self.forces.iter()) {
+            let position = particle.position() + (particle.velocity() + force / particle.inertia() * half) * dt;
+            particle.set_position(position);
+        }
+        let next = compute_forces(particles, &mut forces);
+        //v(t + dt) = v(t) + (a(t) + a(t + dt)) dt / 2
+        for ((particle, &force), &next) in particles.iter_mut().zip(self.forces.iter()).zip(next.iter()) {
+            let velocity = particle.velocity() + (force + next) / particle.inertia() * half;
+            particle.set_velocity(velocity);
+        }
+        self.forces = next;
+    }
+
+    fn reset(&mut self) {
+        self.forces.clear();
+    }
+}
+
+///The second order kick-drift-kick leapfrog scheme.
+///
+///Each step kicks the velocities by half a step, drifts the positions a whole step, and kicks the velocities by the
+///other half with the new forces. This follows the same trajectory as VelocityVerlet, but is written as a
+///composition of kicks and drifts. The force from the closing kick of each step is kept for the opening kick of the
+///next.
+#[derive(Clone, Default)]
+pub struct Leapfrog<V> {
+    forces: Vec<V>,
+}
+
+impl<V> Leapfrog<V> {
+    pub fn new() -> Self {
+        Leapfrog{
+            forces: Vec::new(),
+        }
+    }
+}
+
+impl<V, D> Integrator<V, D> for Leapfrog<V>
+    where V: Vector<D>, D: Float
+{
+    fn step<P, F>(&mut self, particles: &mut [P], dt: D, mut forces: F)
+        where P: IntegrableParticle<V, D>, F: FnMut(&[P])
+    {
+        if self.forces.len() != particles.len() {
+            self.forces = compute_forces(particles, &mut forces);
+        }
+        let half = dt / (D::one() + D::one());
+        kick(particles, &self.forces, half);
+        drift(particles, dt);
+        self.forces = compute_forces(particles, &mut forces);
+        kick(particles, &self.forces, half);
+    }
+
+    fn reset(&mut self) {
+        self.forces.clear();
+    }
+}
@@ -6,5 +6,17 @@
 
 pub mod vector;
 pub mod particle;
+pub mod tree;
+pub mod neighbor;
+pub mod integrate;
+pub mod world;
+#[cfg(feature = "rayon")]
+pub mod parallel;
 pub use vector::*;
 pub use particle::*;
+pub use tree::*;
+pub use neighbor::*;
+pub use integrate::*;
+pub use world::*;
+#[cfg(feature = "rayon")]
+pub use parallel::*;
@@ -0,0 +1,318 @@
+extern crate num;
+use self::num::{Float, NumCast};
+use super::super::particle::*;
+use super::super::vector::*;
+
+///A uniform grid of cells over a box with edges at least as long as a cutoff (a linked-cell list).
+///
+///Once particles are binned with build, any two particles within the cutoff of each other are guaranteed to be in
+///the same or neighbouring cells, so only those pairs need to be checked for short-range interactions. Particles
+///outside of a non-periodic box are binned into the nearest cell on its edge. Distances are measured with the metric
+///of the list, and if the metric has a periodic box, the cells cover that box and wrap around it.
+pub struct CellList<V, D, M = Euclidean> {
+    ///Space the particles are in.
+    metric: M,
+    bounds: Box<V>,
+    cutoff: D,
+    ///Whether cells on opposite faces of the box neighbour each other.
+    periodic: bool,
+    ///Amount of cells along each axis.
+    shape: Vec<usize>,
+    ///First particle in each cell.
+    heads: Vec<Option<usize>>,
+    ///Next particle in the same cell as each particle.
+    next: Vec<Option<usize>>,
+    ///The neighbouring cells of every cell that have an index not below it, including itself.
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl<V, D> CellList<V, D>
+    where V: CartesianVector<D>, D: Float
+{
+    ///Create a cell list over a box for interactions that reach no further than cutoff.
+    pub fn new(bounds: Box<V>, cutoff: D) -> Self {
+        CellList::with_metric(Euclidean, bounds, cutoff)
+    }
+}
+
+impl<V, D> CellList<V, D, Box<V>>
+    where V: CartesianVector<D>, D: Float, Box<V>: Toroid<V>
+{
+    ///Same as new, but cells on opposite faces of the box neighbour each other as with the wrap of Toroid.
+    pub fn new_toroidal(bounds: Box<V>, cutoff: D) -> Self {
+        CellList::with_metric(bounds.clone(), bounds, cutoff)
+    }
+}
+
+impl<V, D, M> CellList<V, D, M>
+    where V: CartesianVector<D>, D: Float, M: Metric<V>
+{
+    ///Same as new, but distances are measured with a metric.
+    ///
+    ///If the metric has a periodic box, the cells cover that box instead of bounds.
+    pub fn with_metric(metric: M, bounds: Box<V>, cutoff: D) -> Self {
+        let (bounds, periodic) = match metric.bounds() {
+            Some(periodic) => (periodic.clone(), true),
+            None => (bounds, false),
+        };
+        let shape: Vec<usize> = (0..V::dimensions()).map(|axis| {
+            let edge = bounds.offset.component(axis).abs() * (D::one() + D::one());
+            (edge / cutoff).floor().to_usize().unwrap_or(1).max(1)
+        }).collect();
+        let cells = shape.iter().product();
+        let mut list = CellList{
+            metric: metric,
+            bounds: bounds,
+            cutoff: cutoff,
+            periodic: periodic,
+            shape: shape,
+            heads: vec![None; cells],
+            next: Vec::new(),
+            neighbors: Vec::with_capacity(cells),
+        };
+        for cell in 0..cells {
+            let neighbors = list.compute_neighbors(cell);
+            list.neighbors.push(neighbors);
+        }
+        list
+    }
+
+    ///The metric distances are measured with.
+    pub fn metric(&self) -> &M {
+        &self.metric
+    }
+
+    ///The box the cells cover.
+    pub fn bounds(&self) -> &Box<V> {
+        &self.bounds
+    }
+
+    ///The cutoff the cells were sized for.
+    pub fn cutoff(&self) -> D {
+        self.cutoff
+    }
+
+    ///Check if cells on opposite faces of the box neighbour each other.
+    pub fn is_toroidal(&self) -> bool {
+        self.periodic
+    }
+
+    ///Amount of cells along each axis.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    ///Bin particles into cells by their current position, replacing whatever was binned before.
+    ///
+    ///Particles are identified by their index in the slice.
+    pub fn build<P>(&mut self, particles: &[P])
+        where P: Position<V>
+    {
+        for head in &mut self.heads {
+            *head = None;
+        }
+        self.next.clear();
+        self.next.resize(particles.len(), None);
+        //Insert in reverse so each cell lists its particles in increasing order.
+        for (index, particle) in particles.iter().enumerate().rev() {
+            let cell = self.cell(&particle.position());
+            self.next[index] = self.heads[cell];
+            self.heads[cell] = Some(index);
+        }
+    }
+
+    ///Get the index of the cell a position is binned into.
+    pub fn cell(&self, pos: &V) -> usize {
+        let mut index = 0;
+        let mut stride = 1;
+        for axis in 0..V::dimensions() {
+            let extent = self.bounds.offset.component(axis).abs();
+            let length = extent * (D::one() + D::one()) / <D as NumCast>::from(self.shape[axis]).unwrap();
+            let relative = (pos.component(axis) - self.bounds.origin.component(axis) + extent) / length;
+            let n = self.shape[axis] as isize;
+            let coordinate = relative.floor().to_isize().unwrap_or(0);
+            let coordinate = if self.periodic {
+                ((coordinate % n) + n) % n
+            } else {
+                coordinate.max(0).min(n - 1)
+            };
+            index += coordinate as usize * stride;
+            stride *= self.shape[axis];
+        }
+        index
+    }
+
+    ///Iterate over the particles binned into a cell.
+    pub fn particles(&self, cell: usize) -> CellParticles<'_> {
+        CellParticles{
+            current: self.heads[cell],
+            next: &self.next,
+        }
+    }
+
+    ///Call f with every pair of particles in the same or neighbouring cells, once per pair.
+    ///
+    ///This includes pairs that are farther apart than the cutoff; use for_each_pair_within to exclude them.
+    pub fn for_each_pair<F>(&self, mut f: F)
+        where F: FnMut(usize, usize)
+    {
+        for (cell, neighbors) in self.neighbors.iter().enumerate() {
+            for i in self.particles(cell) {
+                for &neighbor in neighbors {
+                    if neighbor == cell {
+                        for j in self.particles(cell).skip_while(|&j| j <= i) {
+                            f(i, j);
+                        }
+                    } else {
+                        for j in self.particles(neighbor) {
+                            f(i, j);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    ///Call f with every pair of particles that are within the cutoff of each other, once per pair.
+    ///
+    ///Distance is measured with the metric of the list.
+    pub fn for_each_pair_within<P, F>(&self, particles: &[P], mut f: F)
+        where P: Position<V>, F: FnMut(usize, usize)
+    {
+        let cutoff_squared = self.cutoff * self.cutoff;
+        self.for_each_pair(|i, j| {
+            let delta = self.metric.delta(particles[i].position(), particles[j].position());
+            if delta.displacement_squared() <= cutoff_squared {
+                f(i, j);
+            }
+        });
+    }
+
+    ///Collect every pair of particles that are within the cutoff of each other.
+    pub fn pairs_within<P>(&self, particles: &[P]) -> Vec<(usize, usize)>
+        where P: Position<V>
+    {
+        let mut pairs = Vec::new();
+        self.for_each_pair_within(particles, |i, j| pairs.push((i, j)));
+        pairs
+    }
+
+    fn compute_neighbors(&self, cell: usize) -> Vec<usize> {
+        let dimensions = V::dimensions();
+        let mut coordinates = Vec::with_capacity(dimensions);
+        let mut remaining = cell;
+        for axis in 0..dimensions {
+            coordinates.push((remaining % self.shape[axis]) as isize);
+            remaining /= self.shape[axis];
+        }
+        let mut neighbors = Vec::new();
+        //Each of the 3^n combinations of stepping -1, 0 or 1 cells along every axis.
+        'combinations: for combination in 0..3usize.pow(dimensions as u32) {
+            let mut index = 0;
+            let mut stride = 1;
+            let mut remaining = combination;
+            for (&start, &size) in coordinates.iter().zip(&self.shape) {
+                let n = size as isize;
+                let mut coordinate = start + (remaining % 3) as isize - 1;
+                remaining /= 3;
+                if self.periodic {
+                    coordinate = ((coordinate % n) + n) % n;
+                } else if coordinate < 0 || coordinate >= n {
+                    continue 'combinations;
+                }
+                index += coordinate as usize * stride;
+                stride *= size;
+            }
+            if index >= cell {
+                neighbors.push(index);
+            }
+        }
+        //With fewer than three cells along an axis, wrapping can reach the same cell more than once.
+        neighbors.sort();
+        neighbors.dedup();
+        neighbors
+    }
+}
+
+///Iterator over the indices of the particles in one cell of a CellList.
+pub struct CellParticles<'a> {
+    current: Option<usize>,
+    next: &'a [Option<usize>],
+}
+
+impl<'a> Iterator for CellParticles<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let current = self.current;
+        if let Some(index) = current {
+            self.current = self.next[index];
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+fn brute_force_pairs<V, F>(positions: &[V], cutoff: f64, delta: F) -> Vec<(usize, usize)>
+    where V: Vector<f64>, F: Fn(V) -> V
+{
+    let mut pairs = Vec::new();
+    for i in 0..positions.len() {
+        for j in i + 1..positions.len() {
+            if delta(positions[j] - positions[i]).displacement() <= cutoff {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+fn sorted_pairs(mut pairs: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    for pair in &mut pairs {
+        *pair = (pair.0.min(pair.1), pair.0.max(pair.1));
+    }
+    pairs.sort();
+    pairs
+}
+
+#[test]
+fn cell_list_test() {
+    type V = Cartesian2<f64>;
+    let positions: Vec<V> = scattered(200, 0.0, 10.0);
+    let particles: Vec<BasicParticle<V, f64>> =
+        positions.iter().map(|&position| BasicParticle::new(1.0, position, V::new(0.0, 0.0), 1.0)).collect();
+    let bounds = Box::new(V::new(5.0, 5.0), V::new(5.0, 5.0));
+
+    let mut cells = CellList::new(bounds.clone(), 1.5);
+    cells.build(&particles);
+    assert_eq!(cells.shape(), &[6, 6]);
+    assert_eq!(sorted_pairs(cells.pairs_within(&particles)), brute_force_pairs(&positions, 1.5, |d| d));
+
+    let mut cells = CellList::new_toroidal(bounds.clone(), 1.5);
+    cells.build(&particles);
+    assert_eq!(sorted_pairs(cells.pairs_within(&particles)),
+        brute_force_pairs(&positions, 1.5, |d| bounds.wrap_delta(d)));
+
+    //A metric with a periodic box covers its box rather than the one passed in.
+    let mut cells = CellList::with_metric(&bounds, Box::new(V::new(0.0, 0.0), V::new(1.0, 1.0)), 1.5);
+    cells.build(&particles);
+    assert!(cells.is_toroidal());
+    assert_eq!(cells.shape(), &[6, 6]);
+    assert_eq!(sorted_pairs(cells.pairs_within(&particles)),
+        brute_force_pairs(&positions, 1.5, |d| bounds.wrap_delta(d)));
+}
+
+#[test]
+fn cell_list_few_cells_test() {
+    type V = Cartesian3<f64>;
+    let positions: Vec<V> = scattered(40, 0.0, 4.0);
+    let particles: Vec<BasicParticle<V, f64>> =
+        positions.iter().map(|&position| BasicParticle::new(1.0, position, V::new(0.0, 0.0, 0.0), 1.0)).collect();
+    //Only two cells fit along each axis, so wrapped neighbours must not be visited twice.
+    let bounds = Box::new(V::new(2.0, 2.0, 2.0), V::new(2.0, 2.0, 2.0));
+    let mut cells = CellList::new_toroidal(bounds.clone(), 1.9);
+    cells.build(&particles);
+    assert_eq!(sorted_pairs(cells.pairs_within(&particles)),
+        brute_force_pairs(&positions, 1.9, |d| bounds.wrap_delta(d)));
+}
@@ -0,0 +1,6 @@
+//!Contains structures that find the pairs of particles close enough to take part in short-range interactions
+
+pub mod cell_list;
+pub use self::cell_list::*;
+pub mod neighbor_list;
+pub use self::neighbor_list::*;
@@ -0,0 +1,196 @@
+extern crate num;
+use self::num::Float;
+use super::super::particle::*;
+use super::super::vector::*;
+use super::cell_list::*;
+
+///A Verlet neighbour list that caches the pairs of particles within the cutoff plus a skin distance.
+///
+///As long as no particle has moved more than half of the skin since the pairs were found, every pair within the
+///cutoff is still among the cached pairs, so the list only has to be rebuilt once some particle moves that far.
+///Particles are identified by their index in the slice passed to update, which must not be reordered. Distances are
+///measured with the metric of the list, and if the metric has a periodic box, pairs are found across its walls. A
+///metric that wraps without giving its box, such as an FnMetric made with new, misses the pairs across the walls.
+pub struct NeighborList<V, D, M = Euclidean> {
+    cutoff: D,
+    skin: D,
+    ///Space the particles are in.
+    metric: M,
+    ///Pairs that were within the cutoff plus skin when the list was built.
+    pairs: Vec<(usize, usize)>,
+    ///Positions of the particles when the list was built.
+    reference: Vec<V>,
+    built: bool,
+    rebuilds: usize,
+}
+
+impl<V, D> NeighborList<V, D>
+    where V: CartesianVector<D>, D: Float
+{
+    ///Create a neighbour list for interactions that reach no further than cutoff.
+    pub fn new(cutoff: D, skin: D) -> Self {
+        NeighborList::with_metric(Euclidean, cutoff, skin)
+    }
+}
+
+impl<V, D> NeighborList<V, D, Box<V>>
+    where V: CartesianVector<D>, D: Float, Box<V>: Toroid<V>
+{
+    ///Same as new, but distances are measured with the wrap_delta of a periodic box.
+    pub fn new_toroidal(bounds: Box<V>, cutoff: D, skin: D) -> Self {
+        NeighborList::with_metric(bounds, cutoff, skin)
+    }
+}
+
+impl<V, D, M> NeighborList<V, D, M>
+    where V: CartesianVector<D>, D: Float, M: Metric<V>
+{
+    ///Same as new, but distances are measured with a metric.
+    pub fn with_metric(metric: M, cutoff: D, skin: D) -> Self {
+        NeighborList{
+            cutoff: cutoff,
+            skin: skin,
+            metric: metric,
+            pairs: Vec::new(),
+            reference: Vec::new(),
+            built: false,
+            rebuilds: 0,
+        }
+    }
+
+    ///The metric distances are measured with.
+    pub fn metric(&self) -> &M {
+        &self.metric
+    }
+
+    ///The distance within which pairs interact.
+    pub fn cutoff(&self) -> D {
+        self.cutoff
+    }
+
+    ///The extra distance beyond the cutoff that pairs are cached for.
+    pub fn skin(&self) -> D {
+        self.skin
+    }
+
+    ///Amount of times the list has been built.
+    pub fn rebuilds(&self) -> usize {
+        self.rebuilds
+    }
+
+    ///All pairs that were within the cutoff plus skin when the list was last built, once per pair.
+    pub fn pairs(&self) -> &[(usize, usize)] {
+        &self.pairs
+    }
+
+    ///Check if any particle has moved more than half of the skin since the list was built.
+    pub fn needs_rebuild<P>(&self, particles: &[P]) -> bool
+        where P: Position<V>
+    {
+        if !self.built || particles.len() != self.reference.len() {
+            return true;
+        }
+        let limit = (self.skin / (D::one() + D::one())).powi(2);
+        particles.iter().zip(self.reference.iter()).any(|(particle, &reference)| {
+            self.metric.delta(reference, particle.position()).displacement_squared() > limit
+        })
+    }
+
+    ///Find all pairs within the cutoff plus skin from the current positions of the particles.
+    pub fn rebuild<P>(&mut self, particles: &[P])
+        where P: Position<V>
+    {
+        let bounds = Box::bounding(particles.iter().map(|p| p.position()));
+        let mut cells = CellList::with_metric(&self.metric, bounds, self.cutoff + self.skin);
+        cells.build(particles);
+        self.pairs = cells.pairs_within(particles);
+        self.reference = particles.iter().map(|p| p.position()).collect();
+        self.built = true;
+        self.rebuilds += 1;
+    }
+
+    ///Rebuild the list if needed, returning true if it was rebuilt.
+    ///
+    ///Call this every time before the pairs are used.
+    pub fn update<P>(&mut self, particles: &[P]) -> bool
+        where P: Position<V>
+    {
+        if self.needs_rebuild(particles) {
+            self.rebuild(particles);
+            true
+        } else {
+            false
+        }
+    }
+
+    ///Call f with every cached pair that is currently within the cutoff, once per pair.
+    ///
+    ///The delta passed to f points from the first particle to the second.
+    pub fn for_each_pair_within<P, F>(&self, particles: &[P], mut f: F)
+        where P: Position<V>, F: FnMut(usize, usize, V)
+    {
+        let cutoff_squared = self.cutoff * self.cutoff;
+        for &(i, j) in &self.pairs {
+            let delta = self.metric.delta(particles[i].position(), particles[j].position());
+            if delta.displacement_squared() <= cutoff_squared {
+                f(i, j, delta);
+            }
+        }
+    }
+}
+
+#[test]
+fn neighbor_list_test() {
+    type V = Cartesian2<f64>;
+    let bounds = Box::new(V::new(0.0, 0.0), V::new(5.0, 5.0));
+    let mut particles = scattered_particles(150, -5.0, 5.0, |_| 1.0,
+        |i| V::new((i * 0.271).fract() - 0.5, (i * 0.577).fract() - 0.5));
+    let mut list = NeighborList::new_toroidal(bounds.clone(), 1.0, 0.4);
+    for _ in 0..50 {
+        list.update(&particles);
+        let mut pairs = Vec::new();
+        list.for_each_pair_within(&particles, |i, j, _| pairs.push((i.min(j), i.max(j))));
+        pairs.sort();
+        let mut expected = Vec::new();
+        for i in 0..particles.len() {
+            for j in i + 1..particles.len() {
+                if bounds.wrap_delta(particles[j].position - particles[i].position).displacement() <= 1.0 {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert_eq!(pairs, expected);
+        for particle in &mut particles {
+            particle.advance(0.05);
+            particle.position = bounds.wrap_position(particle.position);
+        }
+    }
+    //Particles move less than 0.036 per step, so the list lasts at least 5 steps after each rebuild.
+    assert!(list.rebuilds() > 1);
+    assert!(list.rebuilds() <= 11);
+}
+
+#[test]
+fn neighbor_list_metric_test() {
+    type V = Cartesian2<f64>;
+    let bounds = Box::new(V::new(0.0, 0.0), V::new(2.0, 2.0));
+    let particles = scattered_particles(40, -2.0, 2.0, |_| 1.0, |_| V::new(0.0, 0.0));
+    //A metric with a periodic box finds the same pairs as a toroidal list.
+    let mut toroidal = NeighborList::new_toroidal(bounds.clone(), 0.8, 0.2);
+    let mut metric = NeighborList::with_metric(&bounds, 0.8, 0.2);
+    toroidal.update(&particles);
+    metric.update(&particles);
+    assert_eq!(toroidal.pairs(), metric.pairs());
+    //A closure metric without a box finds the same pairs as a plain list.
+    let mut plain = NeighborList::new(0.8, 0.2);
+    let mut custom = NeighborList::with_metric(FnMetric::new(|a: V, b: V| b - a), 0.8, 0.2);
+    plain.update(&particles);
+    custom.update(&particles);
+    assert_eq!(plain.pairs(), custom.pairs());
+    assert!(toroidal.pairs().len() > plain.pairs().len());
+    //A wrapping closure metric that gives its box finds the same pairs as a toroidal list.
+    let mut wrapping = NeighborList::with_metric(FnMetric::periodic(|a: V, b: V| bounds.wrap_delta(b - a),
+        bounds.clone()), 0.8, 0.2);
+    wrapping.update(&particles);
+    assert_eq!(toroidal.pairs(), wrapping.pairs());
+}
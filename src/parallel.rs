@@ -0,0 +1,302 @@
+//!Contains force passes that run on all threads with rayon, enabled by the rayon feature
+//!
+//!Particles accumulate force through impulse, which can't be shared between threads, so these passes take a snapshot
+//!of the particles, evaluate an Interaction into separate force buffers, and merge the buffers in an order that only
+//!depends on the amount of particles or pairs. The resulting forces are the same for any amount of threads and can be
+//!applied with ForceBuffer::apply.
+
+extern crate num;
+extern crate rayon;
+use self::num::Float;
+use self::rayon::join;
+use std::cmp::min;
+use std::ops::Range;
+use super::vector::*;
+use super::particle::*;
+use super::neighbor::*;
+
+///Least amount of pairs that are evaluated into one force buffer.
+const CHUNK: usize = 64;
+
+///Most force buffers that a pass is split into, which keeps the memory and merging linear in the amount of particles.
+const LEAVES: usize = 256;
+
+///The state of a particle that interactions read, copied so that it can be shared between threads.
+#[derive(Copy, Clone, Debug)]
+pub struct Snapshot<V, D> {
+    pub position: V,
+    pub velocity: V,
+    pub quanta: D,
+    pub mass: D,
+    pub charge: D,
+    pub inertia: D,
+}
+
+impl<V, D> Snapshot<V, D> {
+    pub fn of<P: ?Sized>(particle: &P) -> Self
+        where P: Position<V> + Velocity<V> + Quanta<D> + Inertia<D>
+    {
+        Snapshot{
+            position: particle.position(),
+            velocity: particle.velocity(),
+            quanta: particle.quanta(),
+            mass: particle.mass(),
+            charge: particle.charge(),
+            inertia: particle.inertia(),
+        }
+    }
+}
+
+impl<V, D> Position<V> for Snapshot<V, D>
+    where V: Copy
+{
+    fn position(&self) -> V {
+        self.position
+    }
+}
+
+impl<V, D> Velocity<V> for Snapshot<V, D>
+    where V: Copy
+{
+    fn velocity(&self) -> V {
+        self.velocity
+    }
+}
+
+impl<V, D> Quanta<D> for Snapshot<V, D>
+    where D: Copy
+{
+    fn quanta(&self) -> D {
+        self.quanta
+    }
+
+    fn gravitational_mass(&self) -> D {
+        self.mass
+    }
+
+    fn electric_charge(&self) -> D {
+        self.charge
+    }
+}
+
+impl<V, D> Inertia<D> for Snapshot<V, D>
+    where D: Copy
+{
+    fn inertia(&self) -> D {
+        self.inertia
+    }
+}
+
+fn snapshots<V, D, T>(particles: &[T]) -> Vec<Snapshot<V, D>>
+    where T: Position<V> + Velocity<V> + Quanta<D> + Inertia<D>
+{
+    particles.iter().map(Snapshot::of).collect()
+}
+
+///Amount of force buffers that a pass over an amount of pairs is split into, which is at most LEAVES buffers of at
+///least CHUNK pairs.
+fn leaves(pairs: usize) -> usize {
+    min(pairs.div_ceil(CHUNK), LEAVES).max(1)
+}
+
+///Evaluate items split evenly into contiguous ranges into force buffers of len particles and sum them.
+fn reduce_items<V, D, F>(items: usize, len: usize, evaluate: &F) -> ForceBuffer<V>
+    where F: Fn(Range<usize>, &mut ForceBuffer<V>) + Sync, V: Vector<D> + Send, D: Float
+{
+    let leaves = leaves(items);
+    let bounds: Vec<usize> = (0..leaves + 1).map(|leaf| items * leaf / leaves).collect();
+    reduce_bounds(&bounds, len, evaluate)
+}
+
+///Split the rows of the all-pairs pass over len particles so that every range holds about the same amount of pairs.
+///
+///Row i holds the len - i - 1 pairs with the particles after it, so the boundary of each range is the first row
+///whose cumulative amount of pairs reaches an even share of all of them.
+fn row_bounds(len: usize) -> Vec<usize> {
+    let pairs = len * len.saturating_sub(1) / 2;
+    let leaves = leaves(pairs);
+    let mut bounds = vec![0];
+    let (mut row, mut before) = (0, 0);
+    for leaf in 1..leaves {
+        let target = pairs * leaf / leaves;
+        while before < target {
+            before += len - row - 1;
+            row += 1;
+        }
+        bounds.push(row);
+    }
+    bounds.push(len);
+    bounds
+}
+
+///Evaluate the ranges between consecutive bounds into force buffers of len particles and sum them.
+///
+///The bounds are split in half recursively, so the order of the sums only depends on the bounds, which only depend
+///on the amount of items.
+fn reduce_bounds<V, D, F>(bounds: &[usize], len: usize, evaluate: &F) -> ForceBuffer<V>
+    where F: Fn(Range<usize>, &mut ForceBuffer<V>) + Sync, V: Vector<D> + Send, D: Float
+{
+    if bounds.len() <= 2 {
+        let mut forces = ForceBuffer::new(len);
+        let items = bounds[0]..bounds[bounds.len() - 1];
+        if !items.is_empty() {
+            evaluate(items, &mut forces);
+        }
+        forces
+    } else {
+        let middle = bounds.len() / 2;
+        let (mut left, right) = join(|| reduce_bounds(&bounds[..middle + 1], len, evaluate),
+            || reduce_bounds(&bounds[middle..], len, evaluate));
+        left.merge(&right);
+        left
+    }
+}
+
+///Find the net force on every particle from every unique pair of particles in parallel.
+///
+///The forces are not applied to the particles until the buffer is applied.
+pub fn par_pair_forces<V, D, T, I: ?Sized>(particles: &[T], interaction: &I) -> ForceBuffer<V>
+    where T: Position<V> + Velocity<V> + Quanta<D> + Inertia<D>, I: Interaction<V, D, Snapshot<V, D>> + Sync,
+    V: Vector<D> + Send + Sync, D: Float + Send + Sync
+{
+    par_pair_forces_in(particles, interaction, &Euclidean)
+}
+
+///Same as par_pair_forces, but the delta between particles is measured with a metric.
+pub fn par_pair_forces_in<V, D, T, I: ?Sized, M: ?Sized>(particles: &[T], interaction: &I, metric: &M)
+    -> ForceBuffer<V>
+    where T: Position<V> + Velocity<V> + Quanta<D> + Inertia<D>, I: Interaction<V, D, Snapshot<V, D>> + Sync,
+    M: Metric<V> + Sync, V: Vector<D> + Send + Sync, D: Float + Send + Sync
+{
+    let snapshots = snapshots(particles);
+    let len = snapshots.len();
+    reduce_bounds(&row_bounds(len), len, &|rows, forces: &mut ForceBuffer<V>| {
+        for i in rows {
+            for j in i + 1..len {
+                let delta = metric.delta(snapshots[i].position, snapshots[j].position);
+                forces.add_pair(i, j, interaction.force_on(&snapshots[i], &snapshots[j], delta));
+            }
+        }
+    })
+}
+
+///Find the net force on every particle from the pairs of a neighbour list that are within its cutoff in parallel.
+///
+///The list must be up to date with the particles, such as after calling update on it.
+pub fn par_neighbor_forces<V, D, M, T, I: ?Sized>(list: &NeighborList<V, D, M>, particles: &[T], interaction: &I)
+    -> ForceBuffer<V>
+    where T: Position<V> + Velocity<V> + Quanta<D> + Inertia<D>, I: Interaction<V, D, Snapshot<V, D>> + Sync,
+    M: Metric<V> + Sync, V: CartesianVector<D> + Send + Sync, D: Float + Send + Sync
+{
+    let snapshots = snapshots(particles);
+    let pairs = list.pairs();
+    let cutoff_squared = list.cutoff() * list.cutoff();
+    reduce_items(pairs.len(), snapshots.len(), &|range, forces: &mut ForceBuffer<V>| {
+        for &(i, j) in &pairs[range] {
+            let delta = list.metric().delta(snapshots[i].position, snapshots[j].position);
+            if delta.displacement_squared() <= cutoff_squared {
+                forces.add_pair(i, j, interaction.force_on(&snapshots[i], &snapshots[j], delta));
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+fn parallel_particles(n: usize) -> Vec<BasicParticle<Cartesian3<f64>, f64>> {
+    scattered_particles(n, -5.0, 5.0, |i| 1.0 + (i * 0.3).fract(), |_| Cartesian3::new(0.0, 0.0, 0.0))
+}
+
+#[cfg(test)]
+fn in_pool<T, F>(threads: usize, f: F) -> T
+    where F: FnOnce() -> T + Send, T: Send
+{
+    rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap().install(f)
+}
+
+#[test]
+fn par_pair_forces_test() {
+    let mut particles = parallel_particles(300);
+    let gravity = Gravity::new(1.0).cutoff(4.0);
+    let forces = par_pair_forces(&particles, &gravity);
+    //Bitwise the same for any amount of threads; snapshots can be moved into a thread pool where particles can't.
+    let snapshots = snapshots(&particles);
+    for &threads in &[1, 2, 3, 8] {
+        let other = in_pool(threads, || par_pair_forces(&snapshots, &gravity));
+        for (a, b) in forces.forces().iter().zip(other.forces()) {
+            assert_eq!((a.x, a.y, a.z), (b.x, b.y, b.z));
+        }
+    }
+    //Matches the serial pass.
+    for_each_pair(&particles, |a, b| interact(a, b, &gravity));
+    for (particle, force) in particles.iter_mut().zip(forces.forces()) {
+        assert!((particle.take_force() - *force).displacement() < 1e-9);
+    }
+    forces.apply(&particles);
+    assert_eq!(particles[7].take_force().x, forces.forces()[7].x);
+}
+
+#[test]
+fn par_neighbor_forces_test() {
+    let mut particles = parallel_particles(500);
+    let bounds = Box::new(Cartesian3::new(0.0, 0.0, 0.0), Cartesian3::new(5.0, 5.0, 5.0));
+    let mut list = NeighborList::new_toroidal(bounds.clone(), 1.5, 0.3);
+    list.update(&particles);
+    let lj = LennardJones::new(0.5, 1.0).cutoff(1.5);
+    let forces = par_neighbor_forces(&list, &particles, &lj);
+    let snapshots = snapshots(&particles);
+    for &threads in &[1, 2, 5] {
+        let other = in_pool(threads, || par_neighbor_forces(&list, &snapshots, &lj));
+        for (a, b) in forces.forces().iter().zip(other.forces()) {
+            assert_eq!((a.x, a.y, a.z), (b.x, b.y, b.z));
+        }
+    }
+    //Matches the all-pairs pass in the same periodic box.
+    let all = par_pair_forces_in(&particles, &lj, &bounds);
+    for (a, b) in forces.forces().iter().zip(all.forces()) {
+        assert!((*a - *b).displacement() < 1e-6 * (1.0 + b.displacement()));
+    }
+    list.for_each_pair_within(&particles, |i, j, delta| lj.apply_delta(&particles[i], &particles[j], delta));
+    for (particle, force) in particles.iter_mut().zip(forces.forces()) {
+        assert!((particle.take_force() - *force).displacement() < 1e-6 * (1.0 + force.displacement()));
+    }
+}
+
+#[test]
+fn reduce_items_test() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    //Every item is evaluated once, and a large pass is split into no more than LEAVES buffers.
+    let leaves = AtomicUsize::new(0);
+    let forces = reduce_items(100000, 2, &|items: Range<usize>, forces: &mut ForceBuffer<Cartesian1<f64>>| {
+        leaves.fetch_add(1, Ordering::SeqCst);
+        assert!(items.len() >= CHUNK);
+        forces.add(0, Cartesian1::new(items.len() as f64));
+    });
+    assert_eq!(leaves.load(Ordering::SeqCst), LEAVES);
+    assert_eq!(forces.forces()[0].x, 100000.0);
+    assert_eq!(forces.forces()[1].x, 0.0);
+    //A small pass has fewer, fuller buffers.
+    leaves.store(0, Ordering::SeqCst);
+    reduce_items(200, 2, &|_: Range<usize>, _: &mut ForceBuffer<Cartesian1<f64>>| {
+        leaves.fetch_add(1, Ordering::SeqCst);
+    });
+    assert_eq!(leaves.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn row_bounds_test() {
+    //Every range of rows holds an even share of the pairs to within the pairs of one row.
+    for &len in &[0, 1, 2, 20, 300, 5000] {
+        let bounds = row_bounds(len);
+        let pairs = len * len.saturating_sub(1) / 2;
+        let leaves = bounds.len() - 1;
+        assert_eq!((bounds[0], bounds[leaves]), (0, len));
+        let mut total = 0;
+        for window in bounds.windows(2) {
+            let held: usize = (window[0]..window[1]).map(|row| len - row - 1).sum();
+            assert!(held + len >= pairs / leaves && held <= pairs / leaves + len, "{} rows {:?}", len, window);
+            total += held;
+        }
+        assert_eq!(total, pairs);
+    }
+    assert_eq!(row_bounds(5000).len(), LEAVES + 1);
+}
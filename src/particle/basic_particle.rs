@@ -3,15 +3,17 @@ use self::num::Float;
 use super::*;
 use super::super::Vector;
 
-use std::cell::UnsafeCell;
+use std::cell::Cell;
 
 ///BasicParticle is the simplest object that implements PhysicsParticle without using constant values.
+///
+///Force is accumulated in a Cell, so a BasicParticle can't be shared between threads; see ForceBuffer for that.
 pub struct BasicParticle<V, D> {
     pub quanta: D,
     pub inertia: D,
     pub position: V,
     pub velocity: V,
-    force: UnsafeCell<V>,
+    force: Cell<V>,
 }
 
 impl<V, D> BasicParticle<V, D> {
@@ -23,21 +25,21 @@ impl<V, D> BasicParticle<V, D> {
             inertia: inertia,
             position: position,
             velocity: velocity,
-            force: UnsafeCell::new(V::zero()),
+            force: Cell::new(V::zero()),
         }
     }
 }
 
 impl<V, D> Clone for BasicParticle<V, D>
-    where V: Clone, D: Clone
+    where V: Copy, D: Clone
 {
     fn clone(&self) -> Self {
         BasicParticle{
             quanta: self.quanta.clone(),
             inertia: self.inertia.clone(),
-            position: self.position.clone(),
-            velocity: self.velocity.clone(),
-            force: UnsafeCell::new(unsafe{(*self.force.get()).clone()}),
+            position: self.position,
+            velocity: self.velocity,
+            force: Cell::new(self.force.get()),
         }
     }
 }
@@ -51,7 +53,7 @@ impl<V, D> Default for BasicParticle<V, D>
             inertia: D::one(),
             position: V::zero(),
             velocity: V::zero(),
-            force: UnsafeCell::new(V::zero()),
+            force: Cell::new(V::zero()),
         }
     }
 }
@@ -92,16 +94,29 @@ impl<V, D> Particle<V, D> for BasicParticle<V, D>
     where V: Vector<D>, D: Float
 {
     fn impulse(&self, vec: &V) {
-        unsafe {
-            *self.force.get() = *self.force.get() + *vec;
-        }
+        self.force.set(self.force.get() + *vec);
     }
 
     fn advance(&mut self, time: D) {
-        let force = unsafe {&mut *self.force.get()};
-        self.velocity = self.velocity + *force / self.inertia() * time;
+        let force = self.force.replace(V::zero());
+        self.velocity = self.velocity + force / self.inertia() * time;
         self.position = self.position + self.velocity * time;
-        *force = V::zero();
+    }
+}
+
+impl<V, D> IntegrableParticle<V, D> for BasicParticle<V, D>
+    where V: Vector<D>, D: Float
+{
+    fn take_force(&mut self) -> V {
+        self.force.replace(V::zero())
+    }
+
+    fn set_position(&mut self, position: V) {
+        self.position = position;
+    }
+
+    fn set_velocity(&mut self, velocity: V) {
+        self.velocity = velocity;
     }
 }
 
@@ -109,3 +124,14 @@ impl<V, D> PhysicsParticle<V, D> for BasicParticle<V, D>
     where V: Vector<D>, D: Float
 {
 }
+
+///Particles at scattered positions with quanta and velocities given by their index, and an inertia of one.
+#[cfg(test)]
+pub fn scattered_particles<V, Q, U>(n: usize, low: f64, high: f64, quanta: Q, velocity: U) -> Vec<BasicParticle<V, f64>>
+    where V: super::super::CartesianVector<f64>, Q: Fn(f64) -> f64, U: Fn(f64) -> V
+{
+    super::super::scattered(n, low, high).into_iter().enumerate().map(|(i, position)| {
+        let i = i as f64;
+        BasicParticle::new(quanta(i), position, velocity(i), 1.0)
+    }).collect()
+}
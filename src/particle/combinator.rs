@@ -0,0 +1,294 @@
+extern crate num;
+use self::num::{Float, NumCast};
+use super::*;
+
+///The sum of two potentials, made by PairPotential::plus.
+#[derive(Copy, Clone, Debug)]
+pub struct Sum<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+impl<A, B, D> PairPotential<D> for Sum<A, B>
+    where A: PairPotential<D>, B: PairPotential<D>, D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        self.first.energy(distance) + self.second.energy(distance)
+    }
+
+    fn force(&self, distance: D) -> D {
+        self.first.force(distance) + self.second.force(distance)
+    }
+}
+
+impl<A, B> ScaledBy for Sum<A, B>
+    where A: ScaledBy, B: ScaledBy<Amount = A::Amount>
+{
+    type Amount = A::Amount;
+}
+
+///A potential multiplied by a factor, made by PairPotential::scaled.
+#[derive(Copy, Clone, Debug)]
+pub struct Scale<P, D> {
+    pub potential: P,
+    pub factor: D,
+}
+
+impl<P, D> PairPotential<D> for Scale<P, D>
+    where P: PairPotential<D>, D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        self.potential.energy(distance) * self.factor
+    }
+
+    fn force(&self, distance: D) -> D {
+        self.potential.force(distance) * self.factor
+    }
+}
+
+impl<P, D> ScaledBy for Scale<P, D>
+    where P: ScaledBy
+{
+    type Amount = P::Amount;
+}
+
+///A potential that is zero at and beyond a cutoff, made by PairPotential::cutoff.
+///
+///The energy jumps at the cutoff unless the potential is already zero there; see Shift and ForceShift.
+#[derive(Copy, Clone, Debug)]
+pub struct Cutoff<P, D> {
+    pub potential: P,
+    pub cutoff: D,
+}
+
+impl<P, D> PairPotential<D> for Cutoff<P, D>
+    where P: PairPotential<D>, D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        if distance < self.cutoff {
+            self.potential.energy(distance)
+        } else {
+            D::zero()
+        }
+    }
+
+    fn force(&self, distance: D) -> D {
+        if distance < self.cutoff {
+            self.potential.force(distance)
+        } else {
+            D::zero()
+        }
+    }
+}
+
+impl<P, D> ScaledBy for Cutoff<P, D>
+    where P: ScaledBy
+{
+    type Amount = P::Amount;
+}
+
+///A potential cut off and shifted so that its energy is zero at the cutoff, made by PairPotential::shifted.
+///
+///The force is unchanged inside of the cutoff, so it still jumps there.
+#[derive(Copy, Clone, Debug)]
+pub struct Shift<P, D> {
+    pub potential: P,
+    pub cutoff: D,
+}
+
+impl<P, D> PairPotential<D> for Shift<P, D>
+    where P: PairPotential<D>, D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        if distance < self.cutoff {
+            self.potential.energy(distance) - self.potential.energy(self.cutoff)
+        } else {
+            D::zero()
+        }
+    }
+
+    fn force(&self, distance: D) -> D {
+        if distance < self.cutoff {
+            self.potential.force(distance)
+        } else {
+            D::zero()
+        }
+    }
+}
+
+impl<P, D> ScaledBy for Shift<P, D>
+    where P: ScaledBy
+{
+    type Amount = P::Amount;
+}
+
+///A potential cut off and shifted so that both its energy and force are zero at the cutoff, made by
+///PairPotential::force_shifted.
+///
+///The force at the cutoff is subtracted from the force everywhere inside of it, which tilts the energy a little.
+#[derive(Copy, Clone, Debug)]
+pub struct ForceShift<P, D> {
+    pub potential: P,
+    pub cutoff: D,
+}
+
+impl<P, D> PairPotential<D> for ForceShift<P, D>
+    where P: PairPotential<D>, D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        if distance < self.cutoff {
+            self.potential.energy(distance) - self.potential.energy(self.cutoff) +
+                (distance - self.cutoff) * self.potential.force(self.cutoff)
+        } else {
+            D::zero()
+        }
+    }
+
+    fn force(&self, distance: D) -> D {
+        if distance < self.cutoff {
+            self.potential.force(distance) - self.potential.force(self.cutoff)
+        } else {
+            D::zero()
+        }
+    }
+}
+
+impl<P, D> ScaledBy for ForceShift<P, D>
+    where P: ScaledBy
+{
+    type Amount = P::Amount;
+}
+
+///A potential that is smoothly switched off between two distances, made by PairPotential::switched.
+///
+///The energy is multiplied by a quintic polynomial in the distance that falls from one at inner to zero at outer with
+///zero first and second derivatives at both ends, so the energy and force are both continuous. The potential is
+///unchanged inside of inner.
+#[derive(Copy, Clone, Debug)]
+pub struct Switch<P, D> {
+    pub potential: P,
+    pub inner: D,
+    pub outer: D,
+}
+
+impl<P, D> Switch<P, D>
+    where D: Float
+{
+    ///The switching polynomial and its derivative at a distance between inner and outer.
+    fn switch(&self, distance: D) -> (D, D) {
+        let constant = |value: f64| <D as NumCast>::from(value).unwrap();
+        let width = self.outer - self.inner;
+        let x = (distance - self.inner) / width;
+        let value = D::one() + x * x * x * (constant(-10.0) + x * (constant(15.0) + x * constant(-6.0)));
+        let slope = x * x * (constant(-30.0) + x * (constant(60.0) + x * constant(-30.0))) / width;
+        (value, slope)
+    }
+}
+
+impl<P, D> PairPotential<D> for Switch<P, D>
+    where P: PairPotential<D>, D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        if distance <= self.inner {
+            self.potential.energy(distance)
+        } else if distance < self.outer {
+            self.potential.energy(distance) * self.switch(distance).0
+        } else {
+            D::zero()
+        }
+    }
+
+    fn force(&self, distance: D) -> D {
+        if distance <= self.inner {
+            self.potential.force(distance)
+        } else if distance < self.outer {
+            let (value, slope) = self.switch(distance);
+            self.potential.force(distance) * value - self.potential.energy(distance) * slope
+        } else {
+            D::zero()
+        }
+    }
+}
+
+impl<P, D> ScaledBy for Switch<P, D>
+    where P: ScaledBy
+{
+    type Amount = P::Amount;
+}
+
+#[cfg(test)]
+fn check_derivative<P>(potential: &P, distances: &[f64])
+    where P: PairPotential<f64>
+{
+    for &r in distances {
+        let derivative = (potential.energy(r + 1e-6) - potential.energy(r - 1e-6)) / 2e-6;
+        assert!((potential.force(r) + derivative).abs() < 1e-5, "force mismatch at {}", r);
+    }
+}
+
+#[test]
+fn sum_scale_test() {
+    let combined = Gravity::new(1.0).plus(Hooke::new(2.0)).scaled(3.0);
+    assert_eq!(combined.energy(2.0), 3.0 * (-0.5 + 4.0));
+    assert_eq!(combined.force(2.0), 3.0 * (-0.25 - 4.0));
+    check_derivative(&combined, &[0.5, 1.0, 2.0]);
+}
+
+#[test]
+fn cutoff_test() {
+    let lj = LennardJones::new(1.0, 1.0);
+    let cut = lj.cutoff(2.5);
+    assert_eq!(cut.energy(2.0), lj.energy(2.0));
+    assert_eq!(cut.energy(2.5), 0.0);
+    assert_eq!(cut.force(3.0), 0.0);
+    //Matches the cutoff of the lennard_jones function.
+    type P = BasicParticle<Cartesian2<f64>, f64>;
+    for &x in &[1.0, 2.4, 2.6] {
+        let mut a = P::default();
+        let mut b = P::new(1.0, Cartesian2::new(x, 0.0), Cartesian2::new(0.0, 0.0), 1.0);
+        lennard_jones(&a, &b, 1.0, 1.0, 2.5);
+        let expected = b.take_force();
+        interact(&a, &b, &cut);
+        a.take_force();
+        assert_eq!(b.take_force().x, expected.x);
+    }
+}
+
+#[test]
+fn shift_test() {
+    let shifted = LennardJones::new(1.0, 1.0).shifted(2.5);
+    assert!(shifted.energy(2.5 - 1e-12).abs() < 1e-10);
+    assert_eq!(shifted.force(2.0), LennardJones::new(1.0, 1.0).force(2.0));
+    check_derivative(&shifted, &[1.0, 1.5, 2.4]);
+
+    let force_shifted = LennardJones::new(1.0, 1.0).force_shifted(2.5);
+    assert!(force_shifted.energy(2.5 - 1e-12).abs() < 1e-10);
+    assert!(force_shifted.force(2.5 - 1e-12).abs() < 1e-10);
+    check_derivative(&force_shifted, &[1.0, 1.5, 2.4]);
+}
+
+#[test]
+fn switch_test() {
+    let lj = LennardJones::new(1.0, 1.0);
+    let switched = lj.switched(2.0, 2.5);
+    assert_eq!(switched.energy(1.5), lj.energy(1.5));
+    assert_eq!(switched.force(1.5), lj.force(1.5));
+    //Continuous energy and force at both ends of the switch.
+    for &r in &[2.0, 2.5] {
+        assert!((switched.energy(r - 1e-9) - switched.energy(r + 1e-9)).abs() < 1e-8);
+        assert!((switched.force(r - 1e-9) - switched.force(r + 1e-9)).abs() < 1e-8);
+    }
+    check_derivative(&switched, &[1.5, 2.1, 2.25, 2.4]);
+}
+
+#[test]
+fn softened_combinator_test() {
+    //The combinators change the tail of the softened core that the _radius pair functions use.
+    let softened = SoftenedGravity::new(1.0, 1.5);
+    let force_shifted = softened.force_shifted(3.0);
+    assert!(force_shifted.force(3.0 - 1e-12).abs() < 1e-10);
+    check_derivative(&force_shifted, &[0.5, 1.5, 2.5]);
+    let switched = softened.switched(2.0, 3.0);
+    assert_eq!(switched.force(0.5), softened.force(0.5));
+    assert_eq!(switched.force(3.5), 0.0);
+}
@@ -0,0 +1,115 @@
+extern crate num;
+use self::num::Zero;
+use super::*;
+use std::ops::{Add, Sub};
+
+///Forces on a slice of particles that are accumulated by index rather than through impulse.
+///
+///Particles accumulate force through shared references, so they can't be shared between threads. A buffer is plain
+///data instead, so every thread can accumulate into a buffer of its own, and the buffers can then be merged and
+///applied to the particles on one thread.
+#[derive(Clone, Debug)]
+pub struct ForceBuffer<V> {
+    forces: Vec<V>,
+}
+
+impl<V> ForceBuffer<V>
+    where V: Copy + Zero + Add<V, Output=V> + Sub<V, Output=V>
+{
+    ///Create a buffer with no force on len particles.
+    pub fn new(len: usize) -> Self {
+        ForceBuffer{
+            forces: vec![V::zero(); len],
+        }
+    }
+
+    ///The amount of particles in the buffer.
+    pub fn len(&self) -> usize {
+        self.forces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.forces.is_empty()
+    }
+
+    ///The net force on each particle, in the order of the particles.
+    pub fn forces(&self) -> &[V] {
+        &self.forces
+    }
+
+    ///Add force to the particle at an index.
+    pub fn add(&mut self, index: usize, force: V) {
+        self.forces[index] = self.forces[index] + force;
+    }
+
+    ///Add force to the particle at lhs and the opposite force to the particle at rhs, as the pair functions do.
+    pub fn add_pair(&mut self, lhs: usize, rhs: usize, force: V) {
+        self.forces[lhs] = self.forces[lhs] + force;
+        self.forces[rhs] = self.forces[rhs] - force;
+    }
+
+    ///Add the forces of another buffer for the same particles to this one.
+    pub fn merge(&mut self, other: &ForceBuffer<V>) {
+        assert_eq!(self.len(), other.len(), "merged force buffers must be for the same particles");
+        for (total, &force) in self.forces.iter_mut().zip(&other.forces) {
+            *total = *total + force;
+        }
+    }
+
+    ///Set the force on every particle back to zero.
+    pub fn clear(&mut self) {
+        for force in &mut self.forces {
+            *force = V::zero();
+        }
+    }
+
+    ///Apply the force on each particle through impulse.
+    pub fn apply<D, P>(&self, particles: &[P])
+        where P: Particle<V, D>
+    {
+        for (particle, force) in particles.iter().zip(&self.forces) {
+            particle.impulse(force);
+        }
+    }
+}
+
+#[test]
+fn force_buffer_test() {
+    type V = Cartesian2<f64>;
+    let positions: Vec<V> = scattered(40, 0.0, 4.0);
+    let mut particles: Vec<BasicParticle<V, f64>> =
+        positions.iter().map(|&position| BasicParticle::new(1.0, position, V::new(0.0, 0.0), 1.0)).collect();
+    let spring = |i: usize, j: usize| (positions[j] - positions[i]) * 0.5;
+
+    //Split the rows of the pairs between threads, which each accumulate into a buffer of their own.
+    let mut total = ForceBuffer::new(particles.len());
+    let threads: Vec<_> = (0..4).map(|t| {
+        let positions = positions.clone();
+        ::std::thread::spawn(move || {
+            let mut buffer = ForceBuffer::new(positions.len());
+            for i in (t..positions.len()).step_by(4) {
+                for j in i + 1..positions.len() {
+                    buffer.add_pair(i, j, (positions[j] - positions[i]) * 0.5);
+                }
+            }
+            buffer
+        })
+    }).collect();
+    for thread in threads {
+        total.merge(&thread.join().unwrap());
+    }
+    total.apply(&particles);
+
+    let mut serial = ForceBuffer::new(particles.len());
+    for i in 0..particles.len() {
+        for j in i + 1..particles.len() {
+            serial.add_pair(i, j, spring(i, j));
+        }
+    }
+    for (particle, force) in particles.iter_mut().zip(serial.forces()) {
+        let applied = particle.take_force();
+        assert!((applied - *force).displacement() < 1e-12);
+    }
+    serial.clear();
+    assert!(serial.forces().iter().all(|force| force.x == 0.0 && force.y == 0.0));
+}
@@ -0,0 +1,387 @@
+extern crate num;
+use self::num::Float;
+use super::*;
+
+///A physical law for the force between two particles, as one value that can be stored and applied uniformly.
+///
+///The particle types are parameters of the trait rather than of its methods so that interactions can be used as trait
+///objects, such as in a Vec<Box<dyn Interaction<V, D, P>>>. The type of rhs defaults to the type of lhs, but they may
+///differ so that two kinds of particles can interact. The delta is passed in rather than computed so that periodic
+///spaces can wrap it first.
+pub trait Interaction<V, D, T1: ?Sized, T2: ?Sized = T1> {
+    ///The force on lhs from rhs, where delta points from lhs to rhs; rhs gets the opposite force.
+    fn force_on(&self, lhs: &T1, rhs: &T2, delta: V) -> V;
+
+    ///Apply the force between two particles, with delta pointing from lhs to rhs.
+    fn apply_delta(&self, lhs: &T1, rhs: &T2, delta: V)
+        where T1: Particle<V, D>, T2: Particle<V, D>, V: Vector<D>, D: Float
+    {
+        let force = self.force_on(lhs, rhs, delta);
+        lhs.impulse(&force);
+        rhs.impulse(&-force);
+    }
+
+    ///Apply the force between two particles based on the difference in their positions.
+    fn apply(&self, lhs: &T1, rhs: &T2)
+        where T1: Particle<V, D>, T2: Particle<V, D>, V: Vector<D>, D: Float
+    {
+        self.apply_delta(lhs, rhs, rhs.position() - lhs.position());
+    }
+
+    ///Apply the force between two particles based on the delta between their positions in a metric.
+    fn apply_in(&self, lhs: &T1, rhs: &T2, metric: &dyn Metric<V>)
+        where T1: Particle<V, D>, T2: Particle<V, D>, V: Vector<D>, D: Float
+    {
+        self.apply_delta(lhs, rhs, metric.delta(lhs.position(), rhs.position()));
+    }
+}
+
+///Make pair potentials into interactions between particles that scale with the amount the potential is ScaledBy.
+macro_rules! pair_interaction {
+    ([$($extra:ident),*] $potential:ty) => {
+        impl<V, D, T1: ?Sized, T2: ?Sized $(, $extra)*> Interaction<V, D, T1, T2> for $potential
+            where $potential: PairPotential<D> + ScaledBy,
+            <$potential as ScaledBy>::Amount: Amount<D, T1> + Amount<D, T2>, V: Vector<D>, D: Float
+        {
+            fn force_on(&self, lhs: &T1, rhs: &T2, delta: V) -> V {
+                potential_force(self, self.product(lhs, rhs), delta)
+            }
+        }
+    };
+}
+
+pair_interaction!([] Gravity<D>);
+pair_interaction!([] SoftenedGravity<D>);
+pair_interaction!([] Hooke<D>);
+pair_interaction!([] HookeEquilibrium<D>);
+pair_interaction!([] LennardJones<D>);
+pair_interaction!([] Morse<D>);
+pair_interaction!([] Yukawa<D>);
+pair_interaction!([] Buckingham<D>);
+pair_interaction!([] Tabulated<D>);
+pair_interaction!([A, B] Sum<A, B>);
+pair_interaction!([Q] Scale<Q, D>);
+pair_interaction!([Q] Cutoff<Q, D>);
+pair_interaction!([Q] Shift<Q, D>);
+pair_interaction!([Q] ForceShift<Q, D>);
+pair_interaction!([Q] Switch<Q, D>);
+
+///The interactions in a list are applied together.
+impl<V, D, T1: ?Sized, T2: ?Sized> Interaction<V, D, T1, T2>
+    for Vec<::std::boxed::Box<dyn Interaction<V, D, T1, T2>>>
+    where V: Vector<D>, D: Float
+{
+    fn force_on(&self, lhs: &T1, rhs: &T2, delta: V) -> V {
+        self.iter().fold(V::zero(), |total, interaction| total + interaction.force_on(lhs, rhs, delta))
+    }
+}
+
+///Gravity softened inside of the sum of the radii of the particles, the interaction of gravitate_radius.
+#[derive(Copy, Clone, Debug)]
+pub struct GravityRadius<D> {
+    pub magnitude: D,
+}
+
+impl<D> GravityRadius<D> {
+    pub fn new(magnitude: D) -> Self {
+        GravityRadius{
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<V, D, T1: ?Sized, T2: ?Sized> Interaction<V, D, T1, T2> for GravityRadius<D>
+    where T1: Mass<D> + Ball<D>, T2: Mass<D> + Ball<D>, V: Vector<D>, D: Float
+{
+    fn force_on(&self, lhs: &T1, rhs: &T2, delta: V) -> V {
+        let radius_squared = (lhs.radius() + rhs.radius()).powi(2);
+        potential_force(&SoftenedGravity::new(radius_squared, self.magnitude), lhs.mass() * rhs.mass(), delta)
+    }
+}
+
+///The velocity dependent force between moving charges, the interaction of lorentz.
+#[derive(Copy, Clone, Debug)]
+pub struct Lorentz<D> {
+    pub magnitude: D,
+}
+
+impl<D> Lorentz<D> {
+    pub fn new(magnitude: D) -> Self {
+        Lorentz{
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<V, D, T1: ?Sized, T2: ?Sized> Interaction<V, D, T1, T2> for Lorentz<D>
+    where T1: Charge<D> + Velocity<V>, T2: Charge<D> + Velocity<V>, V: Vector<D> + CrossVector, D: Float
+{
+    fn force_on(&self, lhs: &T1, rhs: &T2, delta: V) -> V {
+        LorentzRadiusSquared::new(D::zero(), self.magnitude).force_on(lhs, rhs, delta)
+    }
+}
+
+///The lorentz force softened inside of the sum of the radii of the particles, the interaction of lorentz_radius.
+#[derive(Copy, Clone, Debug)]
+pub struct LorentzRadius<D> {
+    pub magnitude: D,
+}
+
+impl<D> LorentzRadius<D> {
+    pub fn new(magnitude: D) -> Self {
+        LorentzRadius{
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<V, D, T1: ?Sized, T2: ?Sized> Interaction<V, D, T1, T2> for LorentzRadius<D>
+    where T1: Charge<D> + Velocity<V> + Ball<D>, T2: Charge<D> + Velocity<V> + Ball<D>, V: Vector<D> + CrossVector,
+    D: Float
+{
+    fn force_on(&self, lhs: &T1, rhs: &T2, delta: V) -> V {
+        let radius_squared = (lhs.radius() + rhs.radius()).powi(2);
+        LorentzRadiusSquared::new(radius_squared, self.magnitude).force_on(lhs, rhs, delta)
+    }
+}
+
+///The lorentz force softened inside of a fixed radius, the interaction of lorentz_radius_squared.
+#[derive(Copy, Clone, Debug)]
+pub struct LorentzRadiusSquared<D> {
+    pub radius_squared: D,
+    pub magnitude: D,
+}
+
+impl<D> LorentzRadiusSquared<D> {
+    pub fn new(radius_squared: D, magnitude: D) -> Self {
+        LorentzRadiusSquared{
+            radius_squared: radius_squared,
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<V, D, T1: ?Sized, T2: ?Sized> Interaction<V, D, T1, T2> for LorentzRadiusSquared<D>
+    where T1: Charge<D> + Velocity<V>, T2: Charge<D> + Velocity<V>, V: Vector<D> + CrossVector, D: Float
+{
+    fn force_on(&self, lhs: &T1, rhs: &T2, delta: V) -> V {
+        let distance = delta.displacement();
+        if distance.is_normal() {
+            //Falls off with the same softened core as gravitate_radius_squared.
+            let scale = -SoftenedGravity::new(self.radius_squared, self.magnitude).force(distance) / distance;
+            -V::cross(&rhs.velocity(), &V::cross(&lhs.velocity(), &delta)) * scale * lhs.charge() * rhs.charge()
+        } else {
+            V::zero()
+        }
+    }
+}
+
+///Inverse square electrostatics where like charges repel, the potential of coulomb.
+#[derive(Copy, Clone, Debug)]
+pub struct Coulomb<D> {
+    pub magnitude: D,
+}
+
+impl<D> Coulomb<D> {
+    pub fn new(magnitude: D) -> Self {
+        Coulomb{
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<D> PairPotential<D> for Coulomb<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        self.magnitude / distance
+    }
+
+    fn force(&self, distance: D) -> D {
+        self.magnitude / distance.powi(2)
+    }
+}
+
+impl<D> ScaledBy for Coulomb<D> {
+    type Amount = ByCharge;
+}
+
+pair_interaction!([] Coulomb<D>);
+
+///Coulomb softened inside of the sum of the radii of the particles, the interaction of coulomb_radius.
+#[derive(Copy, Clone, Debug)]
+pub struct CoulombRadius<D> {
+    pub magnitude: D,
+}
+
+impl<D> CoulombRadius<D> {
+    pub fn new(magnitude: D) -> Self {
+        CoulombRadius{
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<V, D, T1: ?Sized, T2: ?Sized> Interaction<V, D, T1, T2> for CoulombRadius<D>
+    where T1: Charge<D> + Ball<D>, T2: Charge<D> + Ball<D>, V: Vector<D>, D: Float
+{
+    fn force_on(&self, lhs: &T1, rhs: &T2, delta: V) -> V {
+        //Softened gravity with the opposite sign, so that like charges repel.
+        let radius_squared = (lhs.radius() + rhs.radius()).powi(2);
+        -potential_force(&SoftenedGravity::new(radius_squared, self.magnitude), lhs.charge() * rhs.charge(), delta)
+    }
+}
+
+///A particle with a radius for testing the interactions that depend on size.
+#[cfg(test)]
+struct Sphere {
+    particle: BasicParticle<Cartesian3<f64>, f64>,
+    radius: f64,
+}
+
+#[cfg(test)]
+impl Sphere {
+    fn new(quanta: f64, position: Cartesian3<f64>, velocity: Cartesian3<f64>, radius: f64) -> Self {
+        Sphere{
+            particle: BasicParticle::new(quanta, position, velocity, 1.0),
+            radius: radius,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Quanta<f64> for Sphere {
+    fn quanta(&self) -> f64 {
+        self.particle.quanta
+    }
+}
+
+#[cfg(test)]
+impl Inertia<f64> for Sphere {
+    fn inertia(&self) -> f64 {
+        self.particle.inertia
+    }
+}
+
+#[cfg(test)]
+impl Position<Cartesian3<f64>> for Sphere {
+    fn position(&self) -> Cartesian3<f64> {
+        self.particle.position
+    }
+}
+
+#[cfg(test)]
+impl Velocity<Cartesian3<f64>> for Sphere {
+    fn velocity(&self) -> Cartesian3<f64> {
+        self.particle.velocity
+    }
+}
+
+#[cfg(test)]
+impl Particle<Cartesian3<f64>, f64> for Sphere {
+    fn impulse(&self, vec: &Cartesian3<f64>) {
+        self.particle.impulse(vec);
+    }
+
+    fn advance(&mut self, time: f64) {
+        self.particle.advance(time);
+    }
+}
+
+#[cfg(test)]
+impl PhysicsParticle<Cartesian3<f64>, f64> for Sphere {}
+
+#[cfg(test)]
+impl Ball<f64> for Sphere {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+///Take the forces that a pair function applies between two spheres.
+#[cfg(test)]
+fn sphere_forces<F>(distance: f64, f: F) -> (Cartesian3<f64>, Cartesian3<f64>)
+    where F: FnOnce(&Sphere, &Sphere)
+{
+    let mut a = Sphere::new(2.0, Cartesian3::new(0.0, 0.0, 0.0), Cartesian3::new(0.5, 1.0, 0.0), 1.0);
+    let mut b = Sphere::new(3.0, Cartesian3::new(distance, 0.5, 0.0), Cartesian3::new(0.0, -1.0, 0.25), 0.5);
+    f(&a, &b);
+    (a.particle.take_force(), b.particle.take_force())
+}
+
+#[cfg(test)]
+fn assert_same(lhs: (Cartesian3<f64>, Cartesian3<f64>), rhs: (Cartesian3<f64>, Cartesian3<f64>)) {
+    assert!((lhs.0 - rhs.0).displacement() < 1e-12 && (lhs.1 - rhs.1).displacement() < 1e-12);
+}
+
+#[test]
+fn interaction_matches_pair_functions_test() {
+    //Both inside and outside of the combined radius of the spheres.
+    for &distance in &[0.75, 3.0] {
+        assert_same(sphere_forces(distance, |a, b| gravitate(a, b, 1.5)),
+            sphere_forces(distance, |a, b| Gravity::new(1.5).apply(a, b)));
+        assert_same(sphere_forces(distance, |a, b| gravitate_radius(a, b, 1.5)),
+            sphere_forces(distance, |a, b| GravityRadius::new(1.5).apply(a, b)));
+        assert_same(sphere_forces(distance, |a, b| hooke_equilibrium(a, b, 1.0, 1.5)),
+            sphere_forces(distance, |a, b| HookeEquilibrium::new(1.0, 1.5).apply(a, b)));
+        assert_same(sphere_forces(distance, |a, b| lorentz(a, b, 1.5)),
+            sphere_forces(distance, |a, b| Lorentz::new(1.5).apply(a, b)));
+        assert_same(sphere_forces(distance, |a, b| lorentz_radius(a, b, 1.5)),
+            sphere_forces(distance, |a, b| LorentzRadius::new(1.5).apply(a, b)));
+        assert_same(sphere_forces(distance, |a, b| lorentz_radius_squared(a, b, 2.0, 1.5)),
+            sphere_forces(distance, |a, b| LorentzRadiusSquared::new(2.0, 1.5).apply(a, b)));
+    }
+    //Delta is taken as given, such as when it has been wrapped.
+    let wrap = |(a, b): (Cartesian3<f64>, Cartesian3<f64>)| b - a - Cartesian3::new(4.0, 0.0, 0.0);
+    assert_same(sphere_forces(3.0, |a, b| gravitate_delta(a, b, 1.5, wrap)),
+        sphere_forces(3.0, |a, b| Gravity::new(1.5).apply_delta(a, b, wrap((a.position(), b.position())))));
+    let bounds = Box::new(Cartesian3::new(0.0, 0.0, 0.0), Cartesian3::new(2.0, 2.0, 2.0));
+    assert_same(sphere_forces(3.0, |a, b| gravitate_delta(a, b, 1.5, |(a, b)| bounds.wrap_delta(b - a))),
+        sphere_forces(3.0, |a, b| Gravity::new(1.5).apply_in(a, b, &bounds)));
+}
+
+#[test]
+fn interaction_list_test() {
+    let interactions: Vec<::std::boxed::Box<dyn Interaction<Cartesian3<f64>, f64, Sphere>>> = vec![
+        ::std::boxed::Box::new(Gravity::new(1.5)),
+        ::std::boxed::Box::new(LennardJones::new(1.0, 1.0).cutoff(2.5)),
+        ::std::boxed::Box::new(Lorentz::new(0.5)),
+    ];
+    for &distance in &[0.75, 2.0, 3.0] {
+        assert_same(sphere_forces(distance, |a, b| interactions.apply(a, b)), sphere_forces(distance, |a, b| {
+            gravitate(a, b, 1.5);
+            lennard_jones(a, b, 1.0, 1.0, 2.5);
+            lorentz(a, b, 0.5);
+        }));
+    }
+}
+
+#[test]
+fn interaction_between_types_test() {
+    //A sphere and a basic particle in place of another sphere interact the same as the two spheres.
+    let mixed = sphere_forces(3.0, |a, b| {
+        let mut basic = BasicParticle::new(b.quanta(), b.position(), b.velocity(), 1.0);
+        Gravity::new(1.5).apply(a, &basic);
+        lorentz(a, &basic, 0.5);
+        b.impulse(&basic.take_force());
+    });
+    assert_same(mixed, sphere_forces(3.0, |a, b| {
+        Gravity::new(1.5).apply(a, b);
+        lorentz(a, b, 0.5);
+    }));
+}
+
+#[test]
+fn lorentz_radius_squared_test() {
+    //The lorentz force falls off with the cube of the distance outside of the radius and is constant inside of it.
+    let (a, b) = (Cartesian3::new(0.0, 1.0, 0.0), Cartesian3::new(1.0, 0.0, 0.0));
+    let particle = |position, velocity| BasicParticle::new(2.0, position, velocity, 1.0);
+    let origin = particle(Cartesian3::new(0.0, 0.0, 0.0), a);
+    let lorentz = LorentzRadiusSquared::new(4.0, 1.5);
+    for &(distance, scale) in &[(3.0, 1.0 / 27.0), (1.0, 0.25), (0.5, 0.25)] {
+        let other = particle(Cartesian3::new(distance, 0.0, 0.0), b);
+        let delta = Cartesian3::new(distance, 0.0, 0.0);
+        let expected = -Cartesian3::cross(&b, &Cartesian3::cross(&a, &delta)) * 1.5 * 4.0 * scale;
+        assert!((lorentz.force_on(&origin, &other, delta) - expected).displacement() < 1e-12);
+    }
+}
@@ -1,16 +1,80 @@
 ///!Contains traits and methods for the operation of particle physics
 
 pub mod basic_particle;
+pub mod potential;
+pub mod combinator;
+pub mod tabulated;
+pub mod interaction;
+pub mod pairs;
+pub mod force_buffer;
+pub mod soa;
 pub use self::basic_particle::*;
+pub use self::potential::*;
+pub use self::combinator::*;
+pub use self::tabulated::*;
+pub use self::interaction::*;
+pub use self::pairs::*;
+pub use self::force_buffer::*;
+pub use self::soa::*;
 
 extern crate num;
 use self::num::Float;
 use super::vector::*;
 
 ///An object that has quanta
+///
+///Quanta is a single amount that the pair functions without a more specific meaning are scaled by. Anything with
+///quanta also has a Mass and Charge, which are equal to it unless gravitational_mass or electric_charge is overridden,
+///so particles that only implement Quanta work with every interaction.
 pub trait Quanta<D> {
     //Retrieve the quanta of a physics particle.
     fn quanta(&self) -> D;
+
+    //Retrieve the mass that Mass gives for the particle.
+    fn gravitational_mass(&self) -> D {
+        self.quanta()
+    }
+
+    //Retrieve the charge that Charge gives for the particle.
+    fn electric_charge(&self) -> D {
+        self.quanta()
+    }
+}
+
+///An object that has gravitational mass, which is read by the gravitate functions.
+///
+///This is separate from Inertia so that the two can be scaled differently.
+pub trait Mass<D> {
+    //Retrieve the gravitational mass of a particle.
+    fn mass(&self) -> D;
+}
+
+///An object that has electric charge, which is read by the lorentz functions and Boris.
+pub trait Charge<D> {
+    //Retrieve the electric charge of a particle, which may be negative.
+    fn charge(&self) -> D;
+}
+
+///An object that has a magnetic dipole moment.
+pub trait MagneticMoment<V> {
+    //Retrieve the magnetic dipole moment of a particle.
+    fn magnetic_moment(&self) -> V;
+}
+
+impl<D, T: ?Sized> Mass<D> for T
+    where T: Quanta<D>
+{
+    fn mass(&self) -> D {
+        self.gravitational_mass()
+    }
+}
+
+impl<D, T: ?Sized> Charge<D> for T
+    where T: Quanta<D>
+{
+    fn charge(&self) -> D {
+        self.electric_charge()
+    }
 }
 
 ///An object that has inertia
@@ -34,12 +98,47 @@ pub trait Velocity<V> {
 ///An object that has a simple particle motion interface
 pub trait Particle<V, D>: Position<V> + Velocity<V> + Inertia<D> {
     ///Apply force to particle, but it isn't moved forward in time until advance is called.
-    ///This requires interior mutability and unsafe declarations to fulfil.
+    ///This requires interior mutability, such as a Cell, to fulfil.
     fn impulse(&self, vec: &V);
     ///Advance particle forward in time (update position and velocity from net force).
     fn advance(&mut self, time: D);
 }
 
+///A particle whose net force, position, and velocity can be manipulated directly.
+///
+///This allows integrators other than the one built into advance to drive the particle.
+pub trait IntegrableParticle<V, D>: Particle<V, D> {
+    ///Retrieve the net force applied by impulse and reset it to zero as advance would.
+    fn take_force(&mut self) -> V;
+    ///Move the particle to a position.
+    fn set_position(&mut self, position: V);
+    ///Change the velocity of the particle.
+    fn set_velocity(&mut self, velocity: V);
+
+    ///Capture the position and velocity of the particle so that it can be restored later.
+    fn state(&self) -> ParticleState<V> {
+        ParticleState{
+            position: self.position(),
+            velocity: self.velocity(),
+        }
+    }
+
+    ///Return the particle to a state captured earlier.
+    fn restore(&mut self, state: &ParticleState<V>)
+        where V: Clone
+    {
+        self.set_position(state.position.clone());
+        self.set_velocity(state.velocity.clone());
+    }
+}
+
+///The position and velocity of a particle at some point in time.
+#[derive(Copy, Clone)]
+pub struct ParticleState<V> {
+    pub position: V,
+    pub velocity: V,
+}
+
 ///Any particle that implements the necessary traits gains access to all of the capabilities of PhysicsParticle.
 pub trait PhysicsParticle<V, D>: Particle<V, D> + Quanta<D> + Inertia<D>
     where V: Vector<D>, D: Float
@@ -60,60 +159,37 @@ pub trait PhysicsParticle<V, D>: Particle<V, D> + Quanta<D> + Inertia<D>
     fn gravitate_to<T: ?Sized>(&self, center: &T, magnitude: D)
         where T: Quanta<D> + Position<V>
     {
-        //Create delta vector from the particle to the center of attraction.
-        let delta = center.position() - self.position();
-        let distance = delta.displacement();
-        if distance.is_normal() {
-            let force = delta / distance.powi(3) * magnitude * self.quanta() * center.quanta();
-            self.impulse(&force);
-        }
+        self.impulse(&Gravity::new(magnitude).force_on(self, center, center.position() - self.position()));
     }
 
     ///This works the same as gravitate_radius_squared and gravitate_to.
     fn gravitate_radius_to<T: ?Sized>(&self, center: &T, magnitude: D)
         where T: Quanta<D> + Position<V> + Ball<D>
     {
-        //Create delta vector from the particle to the center of attraction.
-        let delta = center.position() - self.position();
-        let distance_squared = delta.displacement_squared();
-        if distance_squared.is_normal() {
-            let force = delta * magnitude * self.quanta() * center.quanta() /
-                if distance_squared > center.radius().powi(2) {
-                    distance_squared.sqrt().powi(3)
-                } else {
-                    center.radius().powi(2)
-                };
-            self.impulse(&force);
-        }
+        let softened = SoftenedGravity::with_radius(center.radius(), magnitude);
+        self.impulse(&softened.force_on(self, center, center.position() - self.position()));
     }
 
     ///Apply spring forces between one particle and a virtual particle that is unaffected.
     fn hooke_to<T: ?Sized>(&self, center: &T, magnitude: D)
         where T: Quanta<D> + Position<V>
     {
-        let delta = center.position() - self.position();
-        let force = delta * magnitude * self.quanta() * center.quanta();
-        self.impulse(&force);
+        self.impulse(&Hooke::new(magnitude).force_on(self, center, center.position() - self.position()));
     }
 
     ///Apply spring forces between one particle and a virtual particle that is unaffected.
     fn hooke_equilibrium_to<T: ?Sized>(&self, center: &T, equilibrium: D, magnitude: D)
         where T: Quanta<D> + Position<V>
     {
-        let delta = center.position() - self.position();
-        let displace = delta.displacement();
-        if displace.is_normal() {
-            let force = delta / displace * (displace - equilibrium) *
-                magnitude * self.quanta() * center.quanta();
-            self.impulse(&force);
-        }
+        let spring = HookeEquilibrium::new(equilibrium, magnitude);
+        self.impulse(&spring.force_on(self, center, center.position() - self.position()));
     }
 
     ///Apply lorentz force to a particle in a field given by a vector with the magnitude and direction of the field.
     fn lorentz_field(&self, field: &V)
         where V: CrossVector
     {
-        let force = V::cross(&self.velocity(), field) * self.quanta() / self.inertia();
+        let force = V::cross(&self.velocity(), field) * self.charge() / self.inertia();
         self.impulse(&force);
     }
 
@@ -121,78 +197,39 @@ pub trait PhysicsParticle<V, D>: Particle<V, D> + Quanta<D> + Inertia<D>
     fn lorentz_to<T: ?Sized>(&self, center: &T, magnitude: D)
         where V: CrossVector, T: Quanta<D> + Position<V> + Velocity<V>
     {
-        let delta = center.position() - self.position();
-        let distance_squared = delta.displacement_squared();
-        if distance_squared.is_normal() {
-            let force = V::cross(&V::cross(&self.velocity(), &delta), &center.velocity()) * magnitude *
-                self.quanta() * center.quanta() / distance_squared.sqrt().powi(3);
-
-            self.impulse(&force);
-        }
+        self.impulse(&Lorentz::new(magnitude).force_on(self, center, center.position() - self.position()));
     }
 
     ///Apply the lorentz force on a virtual particle that is unaffected.
     fn lorentz_radius_to<T: ?Sized>(&self, center: &T, magnitude: D)
         where V: CrossVector, T: Quanta<D> + Position<V> + Velocity<V> + Ball<D>
     {
-        let delta = center.position() - self.position();
-        let distance_squared = delta.displacement_squared();
-        if distance_squared.is_normal() {
-            let force = V::cross(&V::cross(&self.velocity(), &delta), &center.velocity()) * magnitude *
-                self.quanta() * center.quanta() /
-                if distance_squared > center.radius().powi(2) {
-                    distance_squared.sqrt().powi(3)
-                } else {
-                    center.radius().powi(2)
-                };
-
-            self.impulse(&force);
-        }
+        let lorentz = LorentzRadiusSquared::new(center.radius().powi(2), magnitude);
+        self.impulse(&lorentz.force_on(self, center, center.position() - self.position()));
     }
 }
 
-///Apply proper attraction between two physics particles based on their quanta and position.
+///Apply proper attraction between two particles based on their mass and position.
 pub fn gravitate<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D>, D: Float
+    where T1: Particle<V, D> + Mass<D>, T2: Particle<V, D> + Mass<D>, V: Vector<D>, D: Float
 {
-    //Create delta vector between the two positions.
-    let delta = rhs.position() - lhs.position();
-    //Find the distance of the delta vector.
-    let distance = delta.displacement();
-    if distance.is_normal() {
-        //Dividing the delta vector by the distance cubed computes attractive force as per the inverse square law.
-        //The extra degree normalizes the direction vector to a unit vector.
-        let force = delta * magnitude / distance.powi(3) *
-        //Multiply by the two quanta of the objects to compute the final force.
-        lhs.quanta() * rhs.quanta();
-
-        //Force lhs in the direction of force.
-        lhs.impulse(&force);
-        //Apply the inverse force to the rhs similarly.
-        rhs.impulse(&-force);
-    }
+    Gravity::new(magnitude).apply(lhs, rhs);
 }
 
 ///Use a special comp_delta closure to compute the delta from the first to the second param
 pub fn gravitate_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, comp_delta: F)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+    where T1: Particle<V, D> + Mass<D>, T2: Particle<V, D> + Mass<D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
 {
-    //Create delta vector between the two positions.
     let delta = comp_delta((lhs.position(), rhs.position()));
-    //Find the distance of the delta vector.
-    let distance = delta.displacement();
-    if distance.is_normal() {
-        //Dividing the delta vector by the distance cubed computes attractive force as per the inverse square law.
-        //The extra degree normalizes the direction vector to a unit vector.
-        let force = delta * magnitude / distance.powi(3) *
-        //Multiply by the two quanta of the objects to compute the final force.
-        lhs.quanta() * rhs.quanta();
+    Gravity::new(magnitude).apply_delta(lhs, rhs, delta);
+}
 
-        //Force lhs in the direction of force.
-        lhs.impulse(&force);
-        //Apply the inverse force to the rhs similarly.
-        rhs.impulse(&-force);
-    }
+///Same as gravitate, but the delta between the particles is measured with a metric
+pub fn gravitate_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Mass<D>, T2: Particle<V, D> + Mass<D>, V: Vector<D>, D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    Gravity::new(magnitude).apply_delta(lhs, rhs, delta);
 }
 
 #[test]
@@ -207,104 +244,130 @@ fn gravitate_test() {
 
 ///If gravitating at a distance greater than the net radius, then gravitational interaction is applied as if the
 ///particles are point particles. If the distance is less than the radius, then the interaction happens as if the
-///gravitational quanta (mass) is evenly distributed and gravitational flux is used instead, which causes the
+///gravitational mass is evenly distributed and gravitational flux is used instead, which causes the
 ///interaction to become proportional to the radius, meaning that as the radius approaches zero, so does the force.
 pub fn gravitate_radius<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D)
-    where T1: PhysicsParticle<V, D> + Ball<D>, T2: PhysicsParticle<V, D> + Ball<D>, V: Vector<D>, D: Float
+    where T1: Particle<V, D> + Mass<D> + Ball<D>, T2: Particle<V, D> + Mass<D> + Ball<D>, V: Vector<D>, D: Float
 {
-    let delta = rhs.position() - lhs.position();
-    let distance_squared = delta.displacement_squared();
-    if distance_squared.is_normal() {
-        let radius_squared = (lhs.radius() + rhs.radius()).powi(2);
-        //Force is the only thing that changes from normal gravitation.
-        let force = delta * magnitude * lhs.quanta() * rhs.quanta() / if distance_squared > radius_squared {
-            distance_squared.sqrt().powi(3)
-        } else {
-            radius_squared
-        };
-        lhs.impulse(&force);
-        rhs.impulse(&-force);
-    }
+    GravityRadius::new(magnitude).apply(lhs, rhs);
 }
 
 ///Same as gravitate_radius, but uses a special comp_delta closure to compute the delta between the particles
 pub fn gravitate_radius_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, comp_delta: F)
-    where T1: PhysicsParticle<V, D> + Ball<D>, T2: PhysicsParticle<V, D> + Ball<D>, V: Vector<D>, D: Float,
+    where T1: Particle<V, D> + Mass<D> + Ball<D>, T2: Particle<V, D> + Mass<D> + Ball<D>, V: Vector<D>, D: Float,
     F: FnOnce((V, V)) -> V
 {
-    //Create delta vector between the two positions.
     let delta = comp_delta((lhs.position(), rhs.position()));
-    let distance_squared = delta.displacement_squared();
-    if distance_squared.is_normal() {
-        let radius_squared = (lhs.radius() + rhs.radius()).powi(2);
-        //Force is the only thing that changes from normal gravitation.
-        let force = delta * magnitude * lhs.quanta() * rhs.quanta() / if distance_squared > radius_squared {
-            distance_squared.sqrt().powi(3)
-        } else {
-            radius_squared
-        };
-        lhs.impulse(&force);
-        rhs.impulse(&-force);
-    }
+    GravityRadius::new(magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///Same as gravitate_radius, but the delta between the particles is measured with a metric
+pub fn gravitate_radius_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Mass<D> + Ball<D>, T2: Particle<V, D> + Mass<D> + Ball<D>, V: Vector<D>,
+    D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    GravityRadius::new(magnitude).apply_delta(lhs, rhs, delta);
 }
 
 ///This is the same as the radius function, but the sum of the radii squared is passed separately to avoid overhead.
 pub fn gravitate_radius_squared<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, radius_squared: D, magnitude: D)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D>, D: Float
+    where T1: Particle<V, D> + Mass<D>, T2: Particle<V, D> + Mass<D>, V: Vector<D>, D: Float
 {
-    let delta = rhs.position() - lhs.position();
-    let distance_squared = delta.displacement_squared();
-    if distance_squared.is_normal() {
-        //Force is the only thing that changes from normal gravitation.
-        let force = delta * magnitude * lhs.quanta() * rhs.quanta() / if distance_squared > radius_squared {
-            distance_squared.sqrt().powi(3)
-        } else {
-            radius_squared
-        };
-        lhs.impulse(&force);
-        rhs.impulse(&-force);
-    }
+    SoftenedGravity::new(radius_squared, magnitude).apply(lhs, rhs);
 }
 
 ///Same as gravitate_radius_squared, but uses a special comp_delta closure to compute the delta between the particles
 pub fn gravitate_radius_squared_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, radius_squared: D,
     magnitude: D, comp_delta: F)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+    where T1: Particle<V, D> + Mass<D>, T2: Particle<V, D> + Mass<D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
 {
-    //Create delta vector between the two positions.
     let delta = comp_delta((lhs.position(), rhs.position()));
-    let distance_squared = delta.displacement_squared();
-    if distance_squared.is_normal() {
-        //Force is the only thing that changes from normal gravitation.
-        let force = delta * magnitude * lhs.quanta() * rhs.quanta() / if distance_squared > radius_squared {
-            distance_squared.sqrt().powi(3)
-        } else {
-            radius_squared
-        };
-        lhs.impulse(&force);
-        rhs.impulse(&-force);
-    }
+    SoftenedGravity::new(radius_squared, magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///Same as gravitate_radius_squared, but the delta between the particles is measured with a metric
+pub fn gravitate_radius_squared_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, radius_squared: D,
+    magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Mass<D>, T2: Particle<V, D> + Mass<D>, V: Vector<D>, D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    SoftenedGravity::new(radius_squared, magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///Apply electrostatic forces between two particles based on their charge and position.
+///
+///Unlike gravitate, charges of the same sign repel and charges of opposite signs attract.
+pub fn coulomb<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D)
+    where T1: Particle<V, D> + Charge<D>, T2: Particle<V, D> + Charge<D>, V: Vector<D>, D: Float
+{
+    Coulomb::new(magnitude).apply(lhs, rhs);
+}
+
+///Same as coulomb, but uses a comp_delta closure to compute the distance from the first to second parameter
+pub fn coulomb_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, comp_delta: F)
+    where T1: Particle<V, D> + Charge<D>, T2: Particle<V, D> + Charge<D>, V: Vector<D>, D: Float,
+    F: FnOnce((V, V)) -> V
+{
+    let delta = comp_delta((lhs.position(), rhs.position()));
+    Coulomb::new(magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///Same as coulomb, but the delta between the particles is measured with a metric
+pub fn coulomb_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Charge<D>, T2: Particle<V, D> + Charge<D>, V: Vector<D>, D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    Coulomb::new(magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///Same as coulomb, but inside of the sum of the radii the charge is treated as spread out as with gravitate_radius,
+///so the force goes to zero with the distance instead of diverging.
+pub fn coulomb_radius<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D)
+    where T1: Particle<V, D> + Charge<D> + Ball<D>, T2: Particle<V, D> + Charge<D> + Ball<D>, V: Vector<D>, D: Float
+{
+    CoulombRadius::new(magnitude).apply(lhs, rhs);
+}
+
+///Same as coulomb_radius, but uses a comp_delta closure to compute the distance from the first to second parameter
+pub fn coulomb_radius_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, comp_delta: F)
+    where T1: Particle<V, D> + Charge<D> + Ball<D>, T2: Particle<V, D> + Charge<D> + Ball<D>, V: Vector<D>, D: Float,
+    F: FnOnce((V, V)) -> V
+{
+    let delta = comp_delta((lhs.position(), rhs.position()));
+    CoulombRadius::new(magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///Same as coulomb_radius, but the delta between the particles is measured with a metric
+pub fn coulomb_radius_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Charge<D> + Ball<D>, T2: Particle<V, D> + Charge<D> + Ball<D>, V: Vector<D>,
+    D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    CoulombRadius::new(magnitude).apply_delta(lhs, rhs, delta);
 }
 
 ///Apply spring forces between two particles.
 pub fn hooke<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D>, D: Float
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float
 {
-    let delta = rhs.position() - lhs.position();
-    let force = delta * magnitude * lhs.quanta() * rhs.quanta();
-    lhs.impulse(&force);
-    rhs.impulse(&-force);
+    Hooke::new(magnitude).apply(lhs, rhs);
 }
 
 ///Same as hooke, but uses a comp_delta closure to compute the distance from the first to second parameter
 pub fn hooke_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, comp_delta: F)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
 {
-    //Create delta vector between the two positions.
     let delta = comp_delta((lhs.position(), rhs.position()));
-    let force = delta * magnitude * lhs.quanta() * rhs.quanta();
-    lhs.impulse(&force);
-    rhs.impulse(&-force);
+    Hooke::new(magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///Same as hooke, but the delta between the particles is measured with a metric
+pub fn hooke_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    Hooke::new(magnitude).apply_delta(lhs, rhs, delta);
 }
 
 #[test]
@@ -319,70 +382,52 @@ fn hooke_test() {
 
 ///Apply spring forces between two particles with specified equilibrium distance.
 pub fn hooke_equilibrium<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, equilibrium: D, magnitude: D)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D>, D: Float
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float
 {
-    let delta = rhs.position() - lhs.position();
-    let displace = delta.displacement();
-    if displace.is_normal() {
-        let force = delta / displace * magnitude *
-            //We scale such that if displacement is greater than equilibrium, the particles attract proportionally.
-            //Particles with a displacement smaller than equilibrium repel each other.
-            (displace - equilibrium) *
-            lhs.quanta() * rhs.quanta();
-        lhs.impulse(&force);
-        rhs.impulse(&-force);
-    }
+    HookeEquilibrium::new(equilibrium, magnitude).apply(lhs, rhs);
 }
 
 ///Same as hooke_equilibrium, but uses a comp_delta closure to compute the distance from the first to second parameter
 pub fn hooke_equilibrium_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, equilibrium: D, magnitude: D,
     comp_delta: F)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
 {
-    //Create delta vector between the two positions.
     let delta = comp_delta((lhs.position(), rhs.position()));
-    let displace = delta.displacement();
-    if displace.is_normal() {
-        let force = delta / displace * magnitude *
-            //We scale such that if displacement is greater than equilibrium, the particles attract proportionally.
-            //Particles with a displacement smaller than equilibrium repel each other.
-            (displace - equilibrium) *
-            lhs.quanta() * rhs.quanta();
-        lhs.impulse(&force);
-        rhs.impulse(&-force);
-    }
+    HookeEquilibrium::new(equilibrium, magnitude).apply_delta(lhs, rhs, delta);
 }
 
-///Apply lorentz forces between two PhysicsParticle objects based on quanta, position, and velocity.
-pub fn lorentz<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D> + CrossVector, D: Float
+///Same as hooke_equilibrium, but the delta between the particles is measured with a metric
+pub fn hooke_equilibrium_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, equilibrium: D, magnitude: D,
+    metric: &M)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, M: Metric<V>
 {
-    let delta = rhs.position() - lhs.position();
-    let distance = delta.displacement();
-    if distance.is_normal() {
-        let force = V::cross(&rhs.velocity(), &V::cross(&lhs.velocity(), &delta)) *
-            lhs.quanta() * rhs.quanta() / distance.powi(3) * magnitude;
+    let delta = metric.delta(lhs.position(), rhs.position());
+    HookeEquilibrium::new(equilibrium, magnitude).apply_delta(lhs, rhs, delta);
+}
 
-        lhs.impulse(&-force);
-        rhs.impulse(&force);
-    }
+///Apply lorentz forces between two particles based on charge, position, and velocity.
+pub fn lorentz<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D)
+    where T1: Particle<V, D> + Charge<D>, T2: Particle<V, D> + Charge<D>, V: Vector<D> + CrossVector, D: Float
+{
+    Lorentz::new(magnitude).apply(lhs, rhs);
 }
 
 ///Same as lorentz, but uses a comp_delta closure to compute the distance from the first to second parameter
 pub fn lorentz_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, comp_delta: F)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D> + CrossVector, D: Float,
+    where T1: Particle<V, D> + Charge<D>, T2: Particle<V, D> + Charge<D>, V: Vector<D> + CrossVector, D: Float,
     F: FnOnce((V, V)) -> V
 {
-    //Create delta vector between the two positions.
     let delta = comp_delta((lhs.position(), rhs.position()));
-    let distance = delta.displacement();
-    if distance.is_normal() {
-        let force = V::cross(&rhs.velocity(), &V::cross(&lhs.velocity(), &delta)) *
-            lhs.quanta() * rhs.quanta() / distance.powi(3) * magnitude;
+    Lorentz::new(magnitude).apply_delta(lhs, rhs, delta);
+}
 
-        lhs.impulse(&-force);
-        rhs.impulse(&force);
-    }
+///Same as lorentz, but the delta between the particles is measured with a metric
+pub fn lorentz_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Charge<D>, T2: Particle<V, D> + Charge<D>, V: Vector<D> + CrossVector,
+    D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    Lorentz::new(magnitude).apply_delta(lhs, rhs, delta);
 }
 
 #[test]
@@ -395,89 +440,608 @@ fn lorentz_test() {
     b.advance(1.0);
 }
 
-///Apply lorentz forces between two PhysicsParticle + Ball objects assuming uniformly distributed quanta.
+///Apply lorentz forces between two particles with a Ball assuming uniformly distributed charge.
 pub fn lorentz_radius<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D)
-    where T1: PhysicsParticle<V, D> + Ball<D>, T2: PhysicsParticle<V, D> + Ball<D>, V: Vector<D> + CrossVector, D: Float
+    where T1: Particle<V, D> + Charge<D> + Ball<D>, T2: Particle<V, D> + Charge<D> + Ball<D>,
+    V: Vector<D> + CrossVector, D: Float
 {
-    let delta = rhs.position() - lhs.position();
-    let distance_squared = delta.displacement_squared();
-    if distance_squared.is_normal() {
-        let radius_squared = (lhs.radius() + rhs.radius()).powi(2);
-        let force = V::cross(&rhs.velocity(), &V::cross(&lhs.velocity(), &delta)) * magnitude *
-            lhs.quanta() * rhs.quanta() /
-            if distance_squared > radius_squared {
-                distance_squared.sqrt().powi(3)
-            } else {
-                radius_squared
-            };
-
-        lhs.impulse(&-force);
-        rhs.impulse(&force);
-    }
+    LorentzRadius::new(magnitude).apply(lhs, rhs);
 }
 
 ///Same as lorentz_radius, but uses a comp_delta closure to compute the distance from the first to second parameter
 pub fn lorentz_radius_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, comp_delta: F)
-    where T1: PhysicsParticle<V, D> + Ball<D>, T2: PhysicsParticle<V, D> + Ball<D>, V: Vector<D> + CrossVector,
-    D: Float, F: FnOnce((V, V)) -> V
+    where T1: Particle<V, D> + Charge<D> + Ball<D>, T2: Particle<V, D> + Charge<D> + Ball<D>,
+    V: Vector<D> + CrossVector, D: Float, F: FnOnce((V, V)) -> V
 {
-    //Create delta vector between the two positions.
     let delta = comp_delta((lhs.position(), rhs.position()));
-    let distance_squared = delta.displacement_squared();
-    if distance_squared.is_normal() {
-        let radius_squared = (lhs.radius() + rhs.radius()).powi(2);
-        let force = V::cross(&rhs.velocity(), &V::cross(&lhs.velocity(), &delta)) * magnitude *
-            lhs.quanta() * rhs.quanta() /
-            if distance_squared > radius_squared {
-                distance_squared.sqrt().powi(3)
-            } else {
-                radius_squared
-            };
+    LorentzRadius::new(magnitude).apply_delta(lhs, rhs, delta);
+}
 
-        lhs.impulse(&-force);
-        rhs.impulse(&force);
-    }
+///Same as lorentz_radius, but the delta between the particles is measured with a metric
+pub fn lorentz_radius_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Charge<D> + Ball<D>, T2: Particle<V, D> + Charge<D> + Ball<D>,
+    V: Vector<D> + CrossVector, D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    LorentzRadius::new(magnitude).apply_delta(lhs, rhs, delta);
 }
 
-///Apply lorentz forces between two PhysicsParticle objects using a precomputed net radius.
+///Apply lorentz forces between two particles using a precomputed net radius.
 pub fn lorentz_radius_squared<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, radius_squared: D, magnitude: D)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D> + CrossVector, D: Float
+    where T1: Particle<V, D> + Charge<D>, T2: Particle<V, D> + Charge<D>, V: Vector<D> + CrossVector, D: Float
 {
-    let delta = rhs.position() - lhs.position();
-    let distance_squared = delta.displacement_squared();
-    if distance_squared.is_normal() {
-        let force = V::cross(&rhs.velocity(), &V::cross(&lhs.velocity(), &delta)) * magnitude *
-            lhs.quanta() * rhs.quanta() /
-            if distance_squared > radius_squared {
-                distance_squared.sqrt().powi(3)
-            } else {
-                radius_squared
-            };
-
-        lhs.impulse(&-force);
-        rhs.impulse(&force);
-    }
+    LorentzRadiusSquared::new(radius_squared, magnitude).apply(lhs, rhs);
 }
 
 ///Same as lorentz_radius_squared, but uses a comp_delta closure to compute the distance between the particles
 pub fn lorentz_radius_squared_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, radius_squared: D,
     magnitude: D, comp_delta: F)
-    where T1: PhysicsParticle<V, D>, T2: PhysicsParticle<V, D>, V: Vector<D> + CrossVector, D: Float,
+    where T1: Particle<V, D> + Charge<D>, T2: Particle<V, D> + Charge<D>, V: Vector<D> + CrossVector, D: Float,
     F: FnOnce((V, V)) -> V
 {
-    //Create delta vector between the two positions.
     let delta = comp_delta((lhs.position(), rhs.position()));
-    let distance_squared = delta.displacement_squared();
-    if distance_squared.is_normal() {
-        let force = V::cross(&rhs.velocity(), &V::cross(&lhs.velocity(), &delta)) * magnitude *
-            lhs.quanta() * rhs.quanta() /
-            if distance_squared > radius_squared {
-                distance_squared.sqrt().powi(3)
-            } else {
-                radius_squared
-            };
-
-        lhs.impulse(&-force);
-        rhs.impulse(&force);
+    LorentzRadiusSquared::new(radius_squared, magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///Same as lorentz_radius_squared, but the delta between the particles is measured with a metric
+pub fn lorentz_radius_squared_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, radius_squared: D,
+    magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Charge<D>, T2: Particle<V, D> + Charge<D>, V: Vector<D> + CrossVector,
+    D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    LorentzRadiusSquared::new(radius_squared, magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///The force on lhs from a pair potential scaled by the product of the amounts that both particles have.
+fn potential_force<V, D, P: ?Sized>(potential: &P, product: D, delta: V) -> V
+    where P: PairPotential<D>, V: Vector<D>, D: Float
+{
+    let distance = delta.displacement();
+    if distance.is_normal() {
+        -delta * (potential.force(distance) * product / distance)
+    } else {
+        V::zero()
+    }
+}
+
+///The repulsive force of the Lennard-Jones potential at a distance.
+fn lennard_jones_force<D>(distance: D, sigma: D, epsilon: D) -> D
+    where D: Float
+{
+    let six = (sigma / distance).powi(6);
+    let four = D::one() + D::one() + D::one() + D::one();
+    //24 epsilon (2 (sigma / r)^12 - (sigma / r)^6) / r
+    four * (D::one() + D::one() + four) * epsilon * six * (six + six - D::one()) / distance
+}
+
+///Apply Lennard-Jones forces between two particles that are closer than cutoff.
+///
+///The potential is 4 epsilon ((sigma / r)^12 - (sigma / r)^6), which repels below 2^(1/6) sigma and attracts above it.
+///Like the other pair forces, it is scaled by the quanta of both particles.
+pub fn lennard_jones<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, sigma: D, epsilon: D, cutoff: D)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float
+{
+    LennardJones::new(sigma, epsilon).cutoff(cutoff).apply(lhs, rhs);
+}
+
+///Same as lennard_jones, but uses a comp_delta closure to compute the distance from the first to second parameter
+pub fn lennard_jones_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, sigma: D, epsilon: D, cutoff: D,
+    comp_delta: F)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+{
+    let delta = comp_delta((lhs.position(), rhs.position()));
+    LennardJones::new(sigma, epsilon).cutoff(cutoff).apply_delta(lhs, rhs, delta);
+}
+
+///Same as lennard_jones, but the delta between the particles is measured with a metric
+pub fn lennard_jones_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, sigma: D, epsilon: D, cutoff: D,
+    metric: &M)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    LennardJones::new(sigma, epsilon).cutoff(cutoff).apply_delta(lhs, rhs, delta);
+}
+
+///The repulsive force of the Morse potential at a distance.
+fn morse_force<D>(distance: D, depth: D, width: D, equilibrium: D) -> D
+    where D: Float
+{
+    let decay = (-width * (distance - equilibrium)).exp();
+    (D::one() + D::one()) * depth * width * decay * (decay - D::one())
+}
+
+///Apply Morse forces between two particles, which model a chemical bond.
+///
+///The potential is depth (1 - e^(-width (r - equilibrium)))^2, scaled by the quanta of both particles.
+pub fn morse<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, depth: D, width: D, equilibrium: D)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float
+{
+    Morse::new(depth, width, equilibrium).apply(lhs, rhs);
+}
+
+///Same as morse, but uses a comp_delta closure to compute the distance from the first to second parameter
+pub fn morse_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, depth: D, width: D, equilibrium: D,
+    comp_delta: F)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+{
+    let delta = comp_delta((lhs.position(), rhs.position()));
+    Morse::new(depth, width, equilibrium).apply_delta(lhs, rhs, delta);
+}
+
+///Same as morse, but the delta between the particles is measured with a metric
+pub fn morse_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, depth: D, width: D, equilibrium: D,
+    metric: &M)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    Morse::new(depth, width, equilibrium).apply_delta(lhs, rhs, delta);
+}
+
+///The repulsive force of the Yukawa potential at a distance.
+fn yukawa_force<D>(distance: D, screening: D, magnitude: D) -> D
+    where D: Float
+{
+    magnitude * (-distance / screening).exp() * (D::one() + distance / screening) / distance.powi(2)
+}
+
+///Apply screened Coulomb (Yukawa) forces between two particles based on their quanta.
+///
+///The potential is magnitude q1 q2 e^(-r / screening) / r, so quanta of the same sign repel like charges, but the
+///interaction dies off exponentially beyond the screening length.
+pub fn yukawa<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, screening: D, magnitude: D)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float
+{
+    Yukawa::new(screening, magnitude).apply(lhs, rhs);
+}
+
+///Same as yukawa, but uses a comp_delta closure to compute the distance from the first to second parameter
+pub fn yukawa_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, screening: D, magnitude: D, comp_delta: F)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+{
+    let delta = comp_delta((lhs.position(), rhs.position()));
+    Yukawa::new(screening, magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///Same as yukawa, but the delta between the particles is measured with a metric
+pub fn yukawa_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, screening: D, magnitude: D, metric: &M)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    Yukawa::new(screening, magnitude).apply_delta(lhs, rhs, delta);
+}
+
+///The repulsive force of the Buckingham potential at a distance.
+fn buckingham_force<D>(distance: D, a: D, b: D, c: D) -> D
+    where D: Float
+{
+    let six = D::one() + D::one() + D::one() + D::one() + D::one() + D::one();
+    a * b * (-b * distance).exp() - six * c / distance.powi(7)
+}
+
+///Apply Buckingham forces between two particles.
+///
+///The potential is a e^(-b r) - c / r^6, scaled by the quanta of both particles. Note that it collapses to negative
+///infinity at very short distances, so particles must not be allowed to get past the peak of the potential.
+pub fn buckingham<V, D, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, a: D, b: D, c: D)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float
+{
+    Buckingham::new(a, b, c).apply(lhs, rhs);
+}
+
+///Same as buckingham, but uses a comp_delta closure to compute the distance from the first to second parameter
+pub fn buckingham_delta<V, D, F, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, a: D, b: D, c: D, comp_delta: F)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+{
+    let delta = comp_delta((lhs.position(), rhs.position()));
+    Buckingham::new(a, b, c).apply_delta(lhs, rhs, delta);
+}
+
+///Same as buckingham, but the delta between the particles is measured with a metric
+pub fn buckingham_in<V, D, M: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, a: D, b: D, c: D, metric: &M)
+    where T1: Particle<V, D> + Quanta<D>, T2: Particle<V, D> + Quanta<D>, V: Vector<D>, D: Float, M: Metric<V>
+{
+    let delta = metric.delta(lhs.position(), rhs.position());
+    Buckingham::new(a, b, c).apply_delta(lhs, rhs, delta);
+}
+
+///Find the force on the second of two unit particles a distance apart along x from a pair function.
+#[cfg(test)]
+fn pair_force<F>(distance: f64, f: F) -> f64
+    where F: FnOnce(&BasicParticle<Cartesian2<f64>, f64>, &BasicParticle<Cartesian2<f64>, f64>)
+{
+    type P = BasicParticle<Cartesian2<f64>, f64>;
+    let mut a = P::new(1.0, Cartesian2::new(0.0, 0.0), Cartesian2::new(0.0, 0.0), 1.0);
+    let mut b = P::new(1.0, Cartesian2::new(distance, 0.0), Cartesian2::new(0.0, 0.0), 1.0);
+    f(&a, &b);
+    let (lhs, rhs) = (a.take_force(), b.take_force());
+    assert_eq!((lhs.x, lhs.y), (-rhs.x, -rhs.y));
+    rhs.x
+}
+
+///Check that a pair function applies the negative derivative of its potential.
+#[cfg(test)]
+fn check_pair_force<F, E>(f: F, energy: E)
+    where F: Fn(&BasicParticle<Cartesian2<f64>, f64>, &BasicParticle<Cartesian2<f64>, f64>), E: Fn(f64) -> f64
+{
+    for &r in &[0.9, 1.1, 1.5, 2.2] {
+        let derivative = (energy(r + 1e-6) - energy(r - 1e-6)) / 2e-6;
+        assert!((pair_force(r, &f) + derivative).abs() < 1e-5, "force mismatch at {}", r);
+    }
+}
+
+#[test]
+fn lennard_jones_test() {
+    check_pair_force(|a, b| lennard_jones(a, b, 1.0, 2.0, 2.5), |r| 8.0 * (r.powi(-12) - r.powi(-6)));
+    assert!(pair_force(2.0f64.powf(1.0 / 6.0), |a, b| lennard_jones(a, b, 1.0, 1.0, 2.5)).abs() < 1e-12);
+    assert_eq!(pair_force(2.6, |a, b| lennard_jones(a, b, 1.0, 1.0, 2.5)), 0.0);
+}
+
+#[test]
+fn morse_test() {
+    check_pair_force(|a, b| morse(a, b, 2.0, 1.5, 1.2), |r| 2.0 * (1.0 - (-1.5 * (r - 1.2)).exp()).powi(2));
+    assert_eq!(pair_force(1.2, |a, b| morse(a, b, 2.0, 1.5, 1.2)), 0.0);
+}
+
+#[test]
+fn yukawa_test() {
+    check_pair_force(|a, b| yukawa(a, b, 0.5, 3.0), |r| 3.0 * (-r / 0.5).exp() / r);
+    assert!(pair_force(1.0, |a, b| yukawa(a, b, 0.5, 3.0)) > 0.0);
+}
+
+#[test]
+fn buckingham_test() {
+    check_pair_force(|a, b| buckingham(a, b, 1000.0, 5.0, 2.0), |r| 1000.0 * (-5.0 * r).exp() - 2.0 / r.powi(6));
+}
+
+#[test]
+fn pair_delta_test() {
+    //Across the boundary of a periodic box, the particles are only 0.5 apart.
+    let bounds = Box::new(Cartesian2::new(0.0, 0.0), Cartesian2::new(2.0, 2.0));
+    let wrap = |(a, b): (Cartesian2<f64>, Cartesian2<f64>)| bounds.wrap_delta(b - a);
+    let direct = pair_force(-0.5, |a, b| lennard_jones(a, b, 1.0, 1.0, 2.5));
+    assert_eq!(pair_force(3.5, |a, b| lennard_jones_delta(a, b, 1.0, 1.0, 2.5, wrap)), direct);
+    assert_eq!(pair_force(3.5, |a, b| morse_delta(a, b, 1.0, 1.0, 1.0, wrap)),
+        pair_force(-0.5, |a, b| morse(a, b, 1.0, 1.0, 1.0)));
+    assert_eq!(pair_force(3.5, |a, b| yukawa_delta(a, b, 1.0, 1.0, wrap)),
+        pair_force(-0.5, |a, b| yukawa(a, b, 1.0, 1.0)));
+    assert_eq!(pair_force(3.5, |a, b| buckingham_delta(a, b, 1.0, 1.0, 0.0, wrap)),
+        pair_force(-0.5, |a, b| buckingham(a, b, 1.0, 1.0, 0.0)));
+}
+
+#[test]
+fn pair_in_test() {
+    //Measured in the metric of a periodic box, the particles are only 0.5 apart.
+    let bounds = Box::new(Cartesian2::new(0.0, 0.0), Cartesian2::new(2.0, 2.0));
+    assert_eq!(pair_force(3.5, |a, b| gravitate_in(a, b, 1.0, &bounds)), pair_force(-0.5, |a, b| gravitate(a, b, 1.0)));
+    assert_eq!(pair_force(3.5, |a, b| gravitate_radius_squared_in(a, b, 1.0, 1.0, &bounds)),
+        pair_force(-0.5, |a, b| gravitate_radius_squared(a, b, 1.0, 1.0)));
+    assert_eq!(pair_force(3.5, |a, b| hooke_in(a, b, 1.0, &bounds)), pair_force(-0.5, |a, b| hooke(a, b, 1.0)));
+    assert_eq!(pair_force(3.5, |a, b| hooke_equilibrium_in(a, b, 1.0, 1.0, &bounds)),
+        pair_force(-0.5, |a, b| hooke_equilibrium(a, b, 1.0, 1.0)));
+    assert_eq!(pair_force(3.5, |a, b| lennard_jones_in(a, b, 1.0, 1.0, 2.5, &bounds)),
+        pair_force(-0.5, |a, b| lennard_jones(a, b, 1.0, 1.0, 2.5)));
+    assert_eq!(pair_force(3.5, |a, b| morse_in(a, b, 1.0, 1.0, 1.0, &bounds)),
+        pair_force(-0.5, |a, b| morse(a, b, 1.0, 1.0, 1.0)));
+    assert_eq!(pair_force(3.5, |a, b| yukawa_in(a, b, 1.0, 1.0, &bounds)),
+        pair_force(-0.5, |a, b| yukawa(a, b, 1.0, 1.0)));
+    assert_eq!(pair_force(3.5, |a, b| buckingham_in(a, b, 1.0, 1.0, 0.0, &bounds)),
+        pair_force(-0.5, |a, b| buckingham(a, b, 1.0, 1.0, 0.0)));
+    assert_eq!(pair_force(3.5, |a, b| interact_in(a, b, &Morse::new(1.0, 1.0, 1.0), &bounds)),
+        pair_force(-0.5, |a, b| interact(a, b, &Morse::new(1.0, 1.0, 1.0))));
+    assert_eq!(pair_force(3.5, |a, b| hooke_in(a, b, 1.0, &Euclidean)), pair_force(3.5, |a, b| hooke(a, b, 1.0)));
+}
+
+///A particle with separate mass and charge for testing that each interaction reads the right one; its quanta is zero.
+#[cfg(test)]
+struct Ion {
+    particle: BasicParticle<Cartesian3<f64>, f64>,
+    mass: f64,
+    charge: f64,
+    radius: f64,
+}
+
+#[cfg(test)]
+impl Ion {
+    fn new(mass: f64, charge: f64, position: Cartesian3<f64>, velocity: Cartesian3<f64>) -> Self {
+        Ion{
+            particle: BasicParticle::new(0.0, position, velocity, mass),
+            mass: mass,
+            charge: charge,
+            radius: 0.5,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Quanta<f64> for Ion {
+    fn quanta(&self) -> f64 {
+        self.particle.quanta
+    }
+
+    fn gravitational_mass(&self) -> f64 {
+        self.mass
+    }
+
+    fn electric_charge(&self) -> f64 {
+        self.charge
+    }
+}
+
+#[cfg(test)]
+impl Ball<f64> for Ion {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+#[cfg(test)]
+impl Inertia<f64> for Ion {
+    fn inertia(&self) -> f64 {
+        self.particle.inertia
+    }
+}
+
+#[cfg(test)]
+impl Position<Cartesian3<f64>> for Ion {
+    fn position(&self) -> Cartesian3<f64> {
+        self.particle.position
+    }
+}
+
+#[cfg(test)]
+impl Velocity<Cartesian3<f64>> for Ion {
+    fn velocity(&self) -> Cartesian3<f64> {
+        self.particle.velocity
+    }
+}
+
+#[cfg(test)]
+impl Particle<Cartesian3<f64>, f64> for Ion {
+    fn impulse(&self, vec: &Cartesian3<f64>) {
+        self.particle.impulse(vec);
+    }
+
+    fn advance(&mut self, time: f64) {
+        self.particle.advance(time);
+    }
+}
+
+#[cfg(test)]
+impl IntegrableParticle<Cartesian3<f64>, f64> for Ion {
+    fn take_force(&mut self) -> Cartesian3<f64> {
+        self.particle.take_force()
+    }
+
+    fn set_position(&mut self, position: Cartesian3<f64>) {
+        self.particle.set_position(position);
+    }
+
+    fn set_velocity(&mut self, velocity: Cartesian3<f64>) {
+        self.particle.set_velocity(velocity);
+    }
+}
+
+#[cfg(test)]
+impl PhysicsParticle<Cartesian3<f64>, f64> for Ion {}
+
+#[test]
+fn mass_charge_test() {
+    type P = BasicParticle<Cartesian3<f64>, f64>;
+    let (x, v) = (Cartesian3::new(1.5, 0.5, 0.0), Cartesian3::new(0.0, 1.0, 0.5));
+    let mut a = Ion::new(2.0, -1.0, Cartesian3::new(0.0, 0.0, 0.0), Cartesian3::new(1.0, 0.0, 0.0));
+    let mut b = Ion::new(3.0, 4.0, x, v);
+    //Quanta is both the mass and the charge of a particle that only has quanta.
+    let mut heavy_a = P::new(2.0, a.position(), a.velocity(), 2.0);
+    let mut heavy_b = P::new(3.0, x, v, 3.0);
+    let charged_a = P::new(-1.0, a.position(), a.velocity(), 2.0);
+    let mut charged_b = P::new(4.0, x, v, 3.0);
+    assert_eq!((heavy_a.mass(), charged_a.charge()), (2.0, -1.0));
+
+    gravitate(&a, &b, 1.0);
+    gravitate(&heavy_a, &heavy_b, 1.0);
+    let (ion, basic) = (a.particle.take_force(), heavy_a.take_force());
+    assert_eq!((ion.x, ion.y, ion.z), (basic.x, basic.y, basic.z));
+    b.particle.take_force();
+    heavy_b.take_force();
+
+    lorentz(&a, &b, 1.0);
+    lorentz(&charged_a, &charged_b, 1.0);
+    let (ion, basic) = (b.particle.take_force(), charged_b.take_force());
+    assert_eq!((ion.x, ion.y, ion.z), (basic.x, basic.y, basic.z));
+    a.particle.take_force();
+
+    //The interaction values read the same traits.
+    Gravity::new(1.0).apply(&a, &b);
+    let ion = a.particle.take_force();
+    assert!((ion - Gravity::new(1.0).force_on(&heavy_a, &heavy_b, x)).displacement() < 1e-12);
+    b.particle.take_force();
+    Lorentz::new(1.0).apply(&a, &b);
+    let ion = b.particle.take_force();
+    assert!((ion + Lorentz::new(1.0).force_on(&charged_a, &charged_b, x)).displacement() < 1e-12);
+    a.particle.take_force();
+
+    //So do interact and potential_energy, which are scaled by the amount each potential declares.
+    interact(&a, &b, &Gravity::new(1.0));
+    let ion = a.particle.take_force();
+    assert!((ion - Gravity::new(1.0).force_on(&heavy_a, &heavy_b, x)).displacement() < 1e-12);
+    b.particle.take_force();
+    interact(&a, &b, &Gravity::new(1.0).cutoff(10.0));
+    assert!((a.particle.take_force() - ion).displacement() < 1e-12);
+    b.particle.take_force();
+    assert_eq!(potential_energy(&a, &b, &Gravity::new(1.0)), potential_energy(&heavy_a, &heavy_b, &Gravity::new(1.0)));
+    assert_eq!(potential_energy(&a, &b, &Hooke::new(1.0)), 0.0);
+}
+
+#[test]
+fn mass_charge_world_test() {
+    use super::world::World;
+    let zero = Cartesian3::new(0.0, 0.0, 0.0);
+    //Gravity between the ions balances a uniform electric field on their charges, which only holds when gravity reads
+    //mass and the field reads charge.
+    let mut world = World::new();
+    world.add_particle(Ion::new(2.0, 0.5, Cartesian3::new(1.0, 0.0, 0.0), zero));
+    world.add_particle(Ion::new(1.0, -0.5, zero, zero));
+    world.add_interaction(Gravity::new(1.0));
+    world.add_field(|ion: &Ion| Cartesian3::new(4.0 * ion.charge(), 0.0, 0.0));
+    world.run(10, 0.1);
+    assert_eq!(world.particles[0].position().x, 1.0);
+    assert_eq!(world.particles[1].position().x, 0.0);
+
+    //The same forces through PhysicsParticle.
+    let (a, b) = (&mut world.particles[0], Ion::new(1.0, -0.5, zero, zero));
+    a.gravitate_to(&b, 1.0);
+    a.lorentz_field(&Cartesian3::new(0.0, 0.0, 1.0));
+    assert_eq!(a.take_force().x, -2.0);
+    a.set_velocity(Cartesian3::new(0.0, 1.0, 0.0));
+    a.lorentz_field(&Cartesian3::new(0.0, 0.0, 4.0));
+    assert_eq!(a.take_force().x, 0.5 * 4.0 / 2.0);
+}
+
+///A particle that only implements the traits that existed before Mass and Charge.
+#[cfg(test)]
+struct Legacy(BasicParticle<Cartesian3<f64>, f64>);
+
+#[cfg(test)]
+impl Quanta<f64> for Legacy {
+    fn quanta(&self) -> f64 {
+        self.0.quanta
+    }
+}
+
+#[cfg(test)]
+impl Inertia<f64> for Legacy {
+    fn inertia(&self) -> f64 {
+        self.0.inertia
+    }
+}
+
+#[cfg(test)]
+impl Position<Cartesian3<f64>> for Legacy {
+    fn position(&self) -> Cartesian3<f64> {
+        self.0.position
+    }
+}
+
+#[cfg(test)]
+impl Velocity<Cartesian3<f64>> for Legacy {
+    fn velocity(&self) -> Cartesian3<f64> {
+        self.0.velocity
+    }
+}
+
+#[cfg(test)]
+impl Particle<Cartesian3<f64>, f64> for Legacy {
+    fn impulse(&self, vec: &Cartesian3<f64>) {
+        self.0.impulse(vec);
     }
+
+    fn advance(&mut self, time: f64) {
+        self.0.advance(time);
+    }
+}
+
+#[cfg(test)]
+impl PhysicsParticle<Cartesian3<f64>, f64> for Legacy {}
+
+#[test]
+fn quanta_compatibility_test() {
+    use super::tree::BarnesHut;
+    //Code that is only generic over PhysicsParticle can still use the functions that read mass and charge.
+    fn pair<P: PhysicsParticle<Cartesian3<f64>, f64>>(a: &P, b: &P) {
+        gravitate(a, b, 1.0);
+        lorentz(a, b, 1.0);
+        a.gravitate_to(b, 1.0);
+        a.lorentz_field(&Cartesian3::new(0.0, 0.0, 1.0));
+    }
+    let zero = Cartesian3::new(0.0, 0.0, 0.0);
+    let legacy = |quanta: f64, x: f64, v: f64| {
+        Legacy(BasicParticle::new(quanta, Cartesian3::new(x, 0.0, 0.0), Cartesian3::new(0.0, v, 0.0), 1.0))
+    };
+    let (mut a, mut b) = (legacy(2.0, 0.0, 1.0), legacy(3.0, 2.0, -1.0));
+    let (mut c, mut d) = (BasicParticle::new(2.0, a.position(), a.velocity(), 1.0),
+        BasicParticle::new(3.0, b.position(), b.velocity(), 1.0));
+    assert_eq!((a.mass(), a.charge()), (2.0, 2.0));
+    pair(&a, &b);
+    pair(&c, &d);
+    let (legacy_force, basic_force) = (a.0.take_force(), c.take_force());
+    assert_eq!((legacy_force.x, legacy_force.y), (basic_force.x, basic_force.y));
+    assert_eq!(b.0.take_force().x, d.take_force().x);
+    let mut particles = vec![a, b, legacy(1.0, -3.0, 0.0)];
+    BarnesHut::new(&particles, 0.5).gravitate(&particles, 1.0);
+    assert!(particles[2].0.take_force().x > 0.0);
+    assert_eq!(Legacy(BasicParticle::new(1.0, zero, zero, 1.0)).charge(), 1.0);
+}
+
+#[test]
+fn coulomb_test() {
+    let zero = Cartesian3::new(0.0, 0.0, 0.0);
+    let ion = |charge: f64, x: f64| Ion::new(1.0, charge, Cartesian3::new(x, 0.0, 0.0), zero);
+    //Like charges repel and opposite charges attract.
+    for &(q, sign) in &[(2.0, -1.0), (-2.0, 1.0)] {
+        let mut a = ion(1.0, 0.0);
+        let mut b = ion(q, 2.0);
+        coulomb(&a, &b, 3.0);
+        let (fa, fb) = (a.particle.take_force(), b.particle.take_force());
+        assert!((fa.x - sign * 3.0 * 2.0 / 4.0).abs() < 1e-12);
+        assert_eq!(fa.x, -fb.x);
+    }
+
+    //A neutral dipole pulls itself together, but the pair feels no net force.
+    let mut plus = ion(1.0, -0.1);
+    let mut minus = ion(-1.0, 0.1);
+    coulomb(&plus, &minus, 1.0);
+    let (fp, fm) = (plus.particle.take_force(), minus.particle.take_force());
+    assert!(fp.x > 0.0 && fm.x < 0.0);
+    assert_eq!(fp.x + fm.x, 0.0);
+
+    //A distant charge on the axis of the dipole feels a force that falls off with the cube of the distance.
+    let on_axis = |r: f64| {
+        let mut test = ion(1.0, r);
+        coulomb(&plus, &test, 1.0);
+        coulomb(&minus, &test, 1.0);
+        let force = test.particle.take_force().x;
+        assert!((force - (1.0 / (r + 0.1).powi(2) - 1.0 / (r - 0.1).powi(2))).abs() < 1e-12);
+        force
+    };
+    let (near, far) = (on_axis(10.0), on_axis(20.0));
+    //The nearer negative charge wins, so the test charge is attracted.
+    assert!(near < 0.0);
+    assert!((far / near - 0.125).abs() < 1e-3);
+    //The total force on the dipole from the test charges is equal and opposite.
+    let (fp, fm) = (plus.particle.take_force(), minus.particle.take_force());
+    assert!((fp.x + fm.x + near + far).abs() < 1e-12);
+
+    //The potential reads charge and not quanta, which is zero for an ion.
+    let (mut a, b) = (ion(1.0, 0.0), ion(-2.0, 2.0));
+    interact(&a, &b, &Coulomb::new(3.0));
+    assert!((a.particle.take_force().x - 3.0 * 2.0 / 4.0).abs() < 1e-12);
+    assert_eq!(potential_energy(&a, &b, &Coulomb::new(3.0)), -3.0);
+}
+
+#[test]
+fn coulomb_radius_test() {
+    let zero = Cartesian3::new(0.0, 0.0, 0.0);
+    let ion = |charge: f64, x: f64| Ion::new(1.0, charge, Cartesian3::new(x, 0.0, 0.0), zero);
+    //Outside of the radii, the same as coulomb.
+    let (mut a, b) = (ion(1.0, 0.0), ion(2.0, 1.5));
+    coulomb_radius(&a, &b, 1.0);
+    let softened = a.particle.take_force();
+    coulomb(&a, &b, 1.0);
+    assert_eq!(softened.x, a.particle.take_force().x);
+    //Inside, the force shrinks with the distance.
+    let (mut a, b) = (ion(1.0, 0.0), ion(-2.0, 0.5));
+    coulomb_radius(&a, &b, 1.0);
+    assert!((a.particle.take_force().x - 2.0 * 0.5 / 1.0).abs() < 1e-12);
+    //Across the boundary of a periodic box, the particles are only 0.5 apart.
+    let bounds = Box::new(zero, Cartesian3::new(2.0, 2.0, 2.0));
+    let (mut c, d) = (ion(1.0, 0.0), ion(-2.0, 3.5));
+    coulomb_radius_delta(&c, &d, 1.0, |(a, b)| bounds.wrap_delta(b - a));
+    assert!((c.particle.take_force().x + 2.0 * 0.5 / 1.0).abs() < 1e-12);
+    coulomb_delta(&c, &d, 1.0, |(a, b)| bounds.wrap_delta(b - a));
+    assert!((c.particle.take_force().x + 2.0 / 0.25).abs() < 1e-12);
+    coulomb_radius_in(&c, &d, 1.0, &bounds);
+    assert!((c.particle.take_force().x + 2.0 * 0.5 / 1.0).abs() < 1e-12);
+    coulomb_in(&c, &d, 1.0, &bounds);
+    assert!((c.particle.take_force().x + 2.0 / 0.25).abs() < 1e-12);
 }
@@ -0,0 +1,113 @@
+#[cfg(test)]
+use super::*;
+
+///Call f once for every unique pair of particles in a slice, with the lower index as lhs.
+///
+///The pair functions apply the force to both particles through impulse, so this applies every force exactly once:
+///
+///for_each_pair(&particles, |a, b| gravitate(a, b, 1.0));
+pub fn for_each_pair<T, F>(particles: &[T], mut f: F)
+    where F: FnMut(&T, &T)
+{
+    for (i, lhs) in particles.iter().enumerate() {
+        for rhs in &particles[i + 1..] {
+            f(lhs, rhs);
+        }
+    }
+}
+
+///Same as for_each_pair, but only calls f on pairs for which the predicate is true.
+pub fn for_each_pair_filtered<T, P, F>(particles: &[T], mut predicate: P, mut f: F)
+    where P: FnMut(&T, &T) -> bool, F: FnMut(&T, &T)
+{
+    for_each_pair(particles, |lhs, rhs| if predicate(lhs, rhs) {
+        f(lhs, rhs);
+    });
+}
+
+///Call f once for every pair of a particle from lhs and a particle from rhs, such as between two species.
+///
+///Pairs within either slice are not visited.
+pub fn for_each_cross_pair<T1, T2, F>(lhs: &[T1], rhs: &[T2], mut f: F)
+    where F: FnMut(&T1, &T2)
+{
+    for a in lhs {
+        for b in rhs {
+            f(a, b);
+        }
+    }
+}
+
+///Same as for_each_cross_pair, but only calls f on pairs for which the predicate is true.
+pub fn for_each_cross_pair_filtered<T1, T2, P, F>(lhs: &[T1], rhs: &[T2], mut predicate: P, mut f: F)
+    where P: FnMut(&T1, &T2) -> bool, F: FnMut(&T1, &T2)
+{
+    for_each_cross_pair(lhs, rhs, |a, b| if predicate(a, b) {
+        f(a, b);
+    });
+}
+
+#[cfg(test)]
+fn pair_particles(offset: f64) -> Vec<BasicParticle<Cartesian2<f64>, f64>> {
+    (0..6).map(|i| {
+        let i = i as f64;
+        BasicParticle::new(1.0 + i * 0.1, Cartesian2::new(i + offset, (i * i * 0.3).sin()), Cartesian2::new(0.0, 0.0),
+            1.0)
+    }).collect()
+}
+
+#[test]
+fn for_each_pair_test() {
+    let mut particles = pair_particles(0.0);
+    let mut manual = pair_particles(0.0);
+    let mut count = 0;
+    for_each_pair(&particles, |a, b| {
+        gravitate(a, b, 1.0);
+        count += 1;
+    });
+    assert_eq!(count, 15);
+    for i in 0..manual.len() {
+        for j in i + 1..manual.len() {
+            gravitate(&manual[i], &manual[j], 1.0);
+        }
+    }
+    let mut total = Cartesian2::new(0.0, 0.0);
+    for (p, m) in particles.iter_mut().zip(manual.iter_mut()) {
+        let (force, expected) = (p.take_force(), m.take_force());
+        assert!((force - expected).displacement() < 1e-12);
+        total = total + force;
+    }
+    //Each force is applied once and opposite, so they cancel.
+    assert!(total.displacement() < 1e-12);
+
+    //Only heavy pairs.
+    let mut count = 0;
+    for_each_pair_filtered(&particles, |a, b| a.quanta() + b.quanta() > 2.5, |a, b| {
+        gravitate(a, b, 1.0);
+        count += 1;
+    });
+    assert_eq!(count, 6);
+}
+
+#[test]
+fn for_each_cross_pair_test() {
+    let mut stars = pair_particles(0.0);
+    let mut dark = pair_particles(0.5);
+    let mut count = 0;
+    for_each_cross_pair(&stars, &dark, |a, b| {
+        hooke(a, b, 1.0);
+        count += 1;
+    });
+    assert_eq!(count, 36);
+    let total = stars.iter_mut().chain(dark.iter_mut()).fold(Cartesian2::new(0.0, 0.0), |acc, p| acc + p.take_force());
+    assert!(total.displacement() < 1e-12);
+
+    //Only pairs that are close in x.
+    let mut count = 0;
+    for_each_cross_pair_filtered(&stars, &dark, |a, b| (a.position().x - b.position().x).abs() < 1.0, |a, b| {
+        hooke(a, b, 1.0);
+        count += 1;
+    });
+    assert_eq!(count, 11);
+    assert_eq!(stars[0].take_force().x, 0.5 * stars[0].quanta() * dark[0].quanta());
+}
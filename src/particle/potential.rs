@@ -0,0 +1,582 @@
+extern crate num;
+use self::num::Float;
+use super::*;
+
+///A law for the interaction between two particles that depends only on the distance between them.
+///
+///Both the energy and force are for two particles with a unit amount; when applied between particles they are scaled
+///by the amount of both particles that the potential is ScaledBy, such as their mass for Gravity.
+pub trait PairPotential<D> {
+    ///The potential energy at a distance.
+    fn energy(&self, distance: D) -> D;
+
+    ///The force at a distance, which is the negative of the derivative of energy; positive forces repel.
+    fn force(&self, distance: D) -> D;
+
+    ///Add another potential to this one.
+    fn plus<P>(self, other: P) -> Sum<Self, P>
+        where Self: Sized, P: PairPotential<D>
+    {
+        Sum{
+            first: self,
+            second: other,
+        }
+    }
+
+    ///Multiply the energy and force by a factor.
+    fn scaled(self, factor: D) -> Scale<Self, D>
+        where Self: Sized
+    {
+        Scale{
+            potential: self,
+            factor: factor,
+        }
+    }
+
+    ///Remove the potential at and beyond a cutoff.
+    fn cutoff(self, cutoff: D) -> Cutoff<Self, D>
+        where Self: Sized
+    {
+        Cutoff{
+            potential: self,
+            cutoff: cutoff,
+        }
+    }
+
+    ///Remove the potential at and beyond a cutoff and shift the energy to be zero at the cutoff.
+    fn shifted(self, cutoff: D) -> Shift<Self, D>
+        where Self: Sized
+    {
+        Shift{
+            potential: self,
+            cutoff: cutoff,
+        }
+    }
+
+    ///Remove the potential at and beyond a cutoff and shift the force and energy to be zero at the cutoff.
+    fn force_shifted(self, cutoff: D) -> ForceShift<Self, D>
+        where Self: Sized
+    {
+        ForceShift{
+            potential: self,
+            cutoff: cutoff,
+        }
+    }
+
+    ///Smoothly switch the potential off between inner and outer.
+    fn switched(self, inner: D, outer: D) -> Switch<Self, D>
+        where Self: Sized
+    {
+        Switch{
+            potential: self,
+            inner: inner,
+            outer: outer,
+        }
+    }
+}
+
+impl<D, P: ?Sized> PairPotential<D> for &P
+    where P: PairPotential<D>
+{
+    fn energy(&self, distance: D) -> D {
+        (**self).energy(distance)
+    }
+
+    fn force(&self, distance: D) -> D {
+        (**self).force(distance)
+    }
+}
+
+///An amount that a particle has, named by a marker type such as ByMass, which a pair potential is scaled by.
+pub trait Amount<D, T: ?Sized> {
+    ///Retrieve the amount of a particle.
+    fn of(particle: &T) -> D;
+}
+
+///Scales a pair potential by the Quanta of the particles, which most potentials are.
+#[derive(Copy, Clone, Debug)]
+pub struct ByQuanta;
+
+///Scales a pair potential by the Mass of the particles, such as Gravity.
+#[derive(Copy, Clone, Debug)]
+pub struct ByMass;
+
+///Scales a pair potential by the Charge of the particles.
+#[derive(Copy, Clone, Debug)]
+pub struct ByCharge;
+
+impl<D, T: ?Sized> Amount<D, T> for ByQuanta
+    where T: Quanta<D>
+{
+    fn of(particle: &T) -> D {
+        particle.quanta()
+    }
+}
+
+impl<D, T: ?Sized> Amount<D, T> for ByMass
+    where T: Mass<D>
+{
+    fn of(particle: &T) -> D {
+        particle.mass()
+    }
+}
+
+impl<D, T: ?Sized> Amount<D, T> for ByCharge
+    where T: Charge<D>
+{
+    fn of(particle: &T) -> D {
+        particle.charge()
+    }
+}
+
+///Declares the amount of both particles that a pair potential is scaled by when it is applied between them.
+///
+///Both the Interaction of a potential and interact read this, so they always agree. Combinators are scaled by the same
+///amount as the potentials inside of them, and only potentials scaled by the same amount can be added together.
+pub trait ScaledBy {
+    ///A marker such as ByMass.
+    type Amount;
+
+    ///The product of the amounts of two particles that the potential is scaled by.
+    fn product<D, T1: ?Sized, T2: ?Sized>(&self, lhs: &T1, rhs: &T2) -> D
+        where Self::Amount: Amount<D, T1> + Amount<D, T2>, D: Float
+    {
+        <Self::Amount as Amount<D, T1>>::of(lhs) * <Self::Amount as Amount<D, T2>>::of(rhs)
+    }
+}
+
+impl<P: ?Sized> ScaledBy for &P
+    where P: ScaledBy
+{
+    type Amount = P::Amount;
+}
+
+///Inverse square attraction, the potential of gravitate.
+#[derive(Copy, Clone, Debug)]
+pub struct Gravity<D> {
+    pub magnitude: D,
+}
+
+impl<D> Gravity<D> {
+    pub fn new(magnitude: D) -> Self {
+        Gravity{
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<D> PairPotential<D> for Gravity<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        -self.magnitude / distance
+    }
+
+    fn force(&self, distance: D) -> D {
+        -self.magnitude / distance.powi(2)
+    }
+}
+
+impl<D> ScaledBy for Gravity<D> {
+    type Amount = ByMass;
+}
+
+///Gravity that is softened inside of a radius, the potential of gravitate_radius_squared.
+///
+///Inside of the radius the attraction is proportional to the distance, as if the quanta were spread out. The energy
+///is continuous at the radius. Every _radius and _radius_squared pair function softens its core with this, including
+///coulomb_radius and lorentz_radius. Softening changes the core of a potential rather than its tail, so it is not one
+///of the combinators, but the combinators can be applied on top of it, such as to cut it off.
+#[derive(Copy, Clone, Debug)]
+pub struct SoftenedGravity<D> {
+    pub radius_squared: D,
+    pub magnitude: D,
+}
+
+impl<D> SoftenedGravity<D>
+    where D: Float
+{
+    pub fn new(radius_squared: D, magnitude: D) -> Self {
+        SoftenedGravity{
+            radius_squared: radius_squared,
+            magnitude: magnitude,
+        }
+    }
+
+    ///Soften inside of a radius, such as the sum of the radii of two particles like gravitate_radius does.
+    pub fn with_radius(radius: D, magnitude: D) -> Self {
+        SoftenedGravity::new(radius * radius, magnitude)
+    }
+}
+
+impl<D> PairPotential<D> for SoftenedGravity<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        let distance_squared = distance * distance;
+        if distance_squared > self.radius_squared {
+            -self.magnitude / distance
+        } else {
+            self.magnitude * (distance_squared - self.radius_squared) / (self.radius_squared + self.radius_squared) -
+                self.magnitude / self.radius_squared.sqrt()
+        }
+    }
+
+    fn force(&self, distance: D) -> D {
+        let distance_squared = distance * distance;
+        if distance_squared > self.radius_squared {
+            -self.magnitude / distance_squared
+        } else {
+            -self.magnitude * distance / self.radius_squared
+        }
+    }
+}
+
+impl<D> ScaledBy for SoftenedGravity<D> {
+    type Amount = ByMass;
+}
+
+///A spring with no length, the potential of hooke.
+#[derive(Copy, Clone, Debug)]
+pub struct Hooke<D> {
+    pub magnitude: D,
+}
+
+impl<D> Hooke<D> {
+    pub fn new(magnitude: D) -> Self {
+        Hooke{
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<D> PairPotential<D> for Hooke<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        self.magnitude * distance * distance / (D::one() + D::one())
+    }
+
+    fn force(&self, distance: D) -> D {
+        -self.magnitude * distance
+    }
+}
+
+impl<D> ScaledBy for Hooke<D> {
+    type Amount = ByQuanta;
+}
+
+///A spring that rests at an equilibrium distance, the potential of hooke_equilibrium.
+#[derive(Copy, Clone, Debug)]
+pub struct HookeEquilibrium<D> {
+    pub equilibrium: D,
+    pub magnitude: D,
+}
+
+impl<D> HookeEquilibrium<D> {
+    pub fn new(equilibrium: D, magnitude: D) -> Self {
+        HookeEquilibrium{
+            equilibrium: equilibrium,
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<D> PairPotential<D> for HookeEquilibrium<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        self.magnitude * (distance - self.equilibrium).powi(2) / (D::one() + D::one())
+    }
+
+    fn force(&self, distance: D) -> D {
+        -self.magnitude * (distance - self.equilibrium)
+    }
+}
+
+impl<D> ScaledBy for HookeEquilibrium<D> {
+    type Amount = ByQuanta;
+}
+
+///The potential of lennard_jones without its cutoff.
+#[derive(Copy, Clone, Debug)]
+pub struct LennardJones<D> {
+    pub sigma: D,
+    pub epsilon: D,
+}
+
+impl<D> LennardJones<D> {
+    pub fn new(sigma: D, epsilon: D) -> Self {
+        LennardJones{
+            sigma: sigma,
+            epsilon: epsilon,
+        }
+    }
+}
+
+impl<D> PairPotential<D> for LennardJones<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        let six = (self.sigma / distance).powi(6);
+        (D::one() + D::one() + D::one() + D::one()) * self.epsilon * six * (six - D::one())
+    }
+
+    fn force(&self, distance: D) -> D {
+        lennard_jones_force(distance, self.sigma, self.epsilon)
+    }
+}
+
+impl<D> ScaledBy for LennardJones<D> {
+    type Amount = ByQuanta;
+}
+
+///The potential of morse.
+#[derive(Copy, Clone, Debug)]
+pub struct Morse<D> {
+    pub depth: D,
+    pub width: D,
+    pub equilibrium: D,
+}
+
+impl<D> Morse<D> {
+    pub fn new(depth: D, width: D, equilibrium: D) -> Self {
+        Morse{
+            depth: depth,
+            width: width,
+            equilibrium: equilibrium,
+        }
+    }
+}
+
+impl<D> PairPotential<D> for Morse<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        self.depth * (D::one() - (-self.width * (distance - self.equilibrium)).exp()).powi(2)
+    }
+
+    fn force(&self, distance: D) -> D {
+        morse_force(distance, self.depth, self.width, self.equilibrium)
+    }
+}
+
+impl<D> ScaledBy for Morse<D> {
+    type Amount = ByQuanta;
+}
+
+///The potential of yukawa.
+#[derive(Copy, Clone, Debug)]
+pub struct Yukawa<D> {
+    pub screening: D,
+    pub magnitude: D,
+}
+
+impl<D> Yukawa<D> {
+    pub fn new(screening: D, magnitude: D) -> Self {
+        Yukawa{
+            screening: screening,
+            magnitude: magnitude,
+        }
+    }
+}
+
+impl<D> PairPotential<D> for Yukawa<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        self.magnitude * (-distance / self.screening).exp() / distance
+    }
+
+    fn force(&self, distance: D) -> D {
+        yukawa_force(distance, self.screening, self.magnitude)
+    }
+}
+
+impl<D> ScaledBy for Yukawa<D> {
+    type Amount = ByQuanta;
+}
+
+///The potential of buckingham.
+#[derive(Copy, Clone, Debug)]
+pub struct Buckingham<D> {
+    pub a: D,
+    pub b: D,
+    pub c: D,
+}
+
+impl<D> Buckingham<D> {
+    pub fn new(a: D, b: D, c: D) -> Self {
+        Buckingham{
+            a: a,
+            b: b,
+            c: c,
+        }
+    }
+}
+
+impl<D> PairPotential<D> for Buckingham<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        self.a * (-self.b * distance).exp() - self.c / distance.powi(6)
+    }
+
+    fn force(&self, distance: D) -> D {
+        buckingham_force(distance, self.a, self.b, self.c)
+    }
+}
+
+impl<D> ScaledBy for Buckingham<D> {
+    type Amount = ByQuanta;
+}
+
+///Apply the force of a potential between two particles based on the amount it is ScaledBy and their position.
+pub fn interact<V, D, P: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, potential: &P)
+    where T1: Particle<V, D>, T2: Particle<V, D>, P: PairPotential<D> + ScaledBy,
+    P::Amount: Amount<D, T1> + Amount<D, T2>, V: Vector<D>, D: Float
+{
+    interact_delta(lhs, rhs, potential, |(a, b)| b - a);
+}
+
+///Same as interact, but uses a comp_delta closure to compute the distance from the first to second parameter
+pub fn interact_delta<V, D, F, P: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, potential: &P, comp_delta: F)
+    where T1: Particle<V, D>, T2: Particle<V, D>, P: PairPotential<D> + ScaledBy,
+    P::Amount: Amount<D, T1> + Amount<D, T2>, V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+{
+    let force = potential_force(potential, potential.product(lhs, rhs), comp_delta((lhs.position(), rhs.position())));
+    lhs.impulse(&force);
+    rhs.impulse(&-force);
+}
+
+///Same as interact, but the distance between the particles is measured with a metric
+pub fn interact_in<V, D, M: ?Sized, P: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, potential: &P, metric: &M)
+    where T1: Particle<V, D>, T2: Particle<V, D>, P: PairPotential<D> + ScaledBy,
+    P::Amount: Amount<D, T1> + Amount<D, T2>, M: Metric<V>, V: Vector<D>, D: Float
+{
+    interact_delta(lhs, rhs, potential, |(a, b)| metric.delta(a, b));
+}
+
+///The potential energy between two particles based on the amount the potential is ScaledBy and their position.
+///
+///Particles in the same position have no energy, matching interact, which applies no force to them.
+pub fn potential_energy<V, D, P: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, potential: &P) -> D
+    where T1: Position<V>, T2: Position<V>, P: PairPotential<D> + ScaledBy, P::Amount: Amount<D, T1> + Amount<D, T2>,
+    V: Vector<D>, D: Float
+{
+    potential_energy_delta(lhs, rhs, potential, |(a, b)| b - a)
+}
+
+///Same as potential_energy, but uses a comp_delta closure to compute the distance from the first to second parameter
+pub fn potential_energy_delta<V, D, F, P: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, potential: &P,
+    comp_delta: F) -> D
+    where T1: Position<V>, T2: Position<V>, P: PairPotential<D> + ScaledBy, P::Amount: Amount<D, T1> + Amount<D, T2>,
+    V: Vector<D>, D: Float, F: FnOnce((V, V)) -> V
+{
+    let distance = comp_delta((lhs.position(), rhs.position())).displacement();
+    if distance.is_normal() {
+        potential.energy(distance) * potential.product(lhs, rhs)
+    } else {
+        D::zero()
+    }
+}
+
+///Same as potential_energy, but the distance between the particles is measured with a metric
+pub fn potential_energy_in<V, D, M: ?Sized, P: ?Sized, T1: ?Sized, T2: ?Sized>(lhs: &T1, rhs: &T2, potential: &P,
+    metric: &M) -> D
+    where T1: Position<V>, T2: Position<V>, P: PairPotential<D> + ScaledBy, P::Amount: Amount<D, T1> + Amount<D, T2>,
+    M: Metric<V>, V: Vector<D>, D: Float
+{
+    potential_energy_delta(lhs, rhs, potential, |(a, b)| metric.delta(a, b))
+}
+
+#[cfg(test)]
+type P = BasicParticle<Cartesian2<f64>, f64>;
+
+///Take the forces a pair function applies between two particles.
+#[cfg(test)]
+fn pair_forces<F>(f: F) -> (Cartesian2<f64>, Cartesian2<f64>)
+    where F: FnOnce(&P, &P)
+{
+    let mut a = P::new(2.0, Cartesian2::new(0.5, -0.25), Cartesian2::new(0.0, 0.0), 1.0);
+    let mut b = P::new(3.0, Cartesian2::new(1.25, 0.75), Cartesian2::new(0.0, 0.0), 1.0);
+    f(&a, &b);
+    (a.take_force(), b.take_force())
+}
+
+#[cfg(test)]
+fn assert_same_forces(lhs: (Cartesian2<f64>, Cartesian2<f64>), rhs: (Cartesian2<f64>, Cartesian2<f64>)) {
+    assert!((lhs.0 - rhs.0).displacement() < 1e-12 && (lhs.1 - rhs.1).displacement() < 1e-12);
+}
+
+#[test]
+fn potential_matches_pair_functions_test() {
+    assert_same_forces(pair_forces(|a, b| gravitate(a, b, 1.5)),
+        pair_forces(|a, b| interact(a, b, &Gravity::new(1.5))));
+    assert_same_forces(pair_forces(|a, b| hooke(a, b, 1.5)), pair_forces(|a, b| interact(a, b, &Hooke::new(1.5))));
+    assert_same_forces(pair_forces(|a, b| hooke_equilibrium(a, b, 2.0, 1.5)),
+        pair_forces(|a, b| interact(a, b, &HookeEquilibrium::new(2.0, 1.5))));
+    for &radius_squared in &[0.5, 4.0] {
+        assert_same_forces(pair_forces(|a, b| gravitate_radius_squared(a, b, radius_squared, 1.5)),
+            pair_forces(|a, b| interact(a, b, &SoftenedGravity::new(radius_squared, 1.5))));
+    }
+    assert_same_forces(pair_forces(|a, b| lennard_jones(a, b, 1.0, 2.0, 10.0)),
+        pair_forces(|a, b| interact(a, b, &LennardJones::new(1.0, 2.0))));
+    assert_same_forces(pair_forces(|a, b| morse(a, b, 1.0, 2.0, 0.5)),
+        pair_forces(|a, b| interact(a, b, &Morse::new(1.0, 2.0, 0.5))));
+    assert_same_forces(pair_forces(|a, b| yukawa(a, b, 1.0, 2.0)),
+        pair_forces(|a, b| interact(a, b, &Yukawa::new(1.0, 2.0))));
+    assert_same_forces(pair_forces(|a, b| buckingham(a, b, 1.0, 2.0, 0.5)),
+        pair_forces(|a, b| interact(a, b, &Buckingham::new(1.0, 2.0, 0.5))));
+}
+
+#[test]
+fn potential_force_derivative_test() {
+    let potentials: Vec<::std::boxed::Box<dyn PairPotential<f64>>> = vec![
+        ::std::boxed::Box::new(Gravity::new(1.5)),
+        ::std::boxed::Box::new(SoftenedGravity::new(1.0, 1.5)),
+        ::std::boxed::Box::new(SoftenedGravity::new(4.0, 1.5)),
+        ::std::boxed::Box::new(Hooke::new(1.5)),
+        ::std::boxed::Box::new(HookeEquilibrium::new(1.2, 1.5)),
+        ::std::boxed::Box::new(LennardJones::new(1.0, 2.0)),
+        ::std::boxed::Box::new(Morse::new(1.0, 2.0, 1.2)),
+        ::std::boxed::Box::new(Yukawa::new(0.5, 2.0)),
+        ::std::boxed::Box::new(Buckingham::new(1000.0, 5.0, 2.0)),
+        ::std::boxed::Box::new(Coulomb::new(1.5)),
+    ];
+    for potential in &potentials {
+        for &r in &[0.9, 1.3, 1.7, 2.6] {
+            let derivative = (potential.energy(r + 1e-6) - potential.energy(r - 1e-6)) / 2e-6;
+            assert!((potential.force(r) + derivative).abs() < 1e-5, "force mismatch at {}", r);
+        }
+    }
+    //Softened gravity must not jump in energy at its radius.
+    let softened = SoftenedGravity::new(4.0, 1.5);
+    assert!((softened.energy(2.0 - 1e-9) - softened.energy(2.0 + 1e-9)).abs() < 1e-8);
+}
+
+#[test]
+fn potential_energy_conservation_test() {
+    //A spinning pair of particles on a spring conserves the kinetic energy plus the energy of the potential.
+    use super::super::integrate::{Integrator, VelocityVerlet};
+    let spring = HookeEquilibrium::new(1.0, 4.0);
+    let mut particles = vec![
+        P::new(1.0, Cartesian2::new(-0.6, 0.0), Cartesian2::new(0.0, -0.5), 1.0),
+        P::new(1.0, Cartesian2::new(0.6, 0.0), Cartesian2::new(0.0, 0.5), 1.0),
+    ];
+    let energy = |particles: &[P]| {
+        particles.iter().map(|p| 0.5 * p.inertia * p.velocity.displacement_squared()).sum::<f64>() +
+            potential_energy(&particles[0], &particles[1], &spring)
+    };
+    let initial = energy(&particles);
+    let mut integrator = VelocityVerlet::new();
+    for _ in 0..10000 {
+        integrator.step(&mut particles, 0.001, |p: &[P]| interact(&p[0], &p[1], &spring));
+    }
+    assert!(((energy(&particles) - initial) / initial).abs() < 1e-5);
+    let wrap = |(a, b): (Cartesian2<f64>, Cartesian2<f64>)| b - a;
+    assert_eq!(potential_energy_delta(&particles[0], &particles[1], &spring, wrap),
+        potential_energy(&particles[0], &particles[1], &spring));
+    assert_eq!(potential_energy_in(&particles[0], &particles[1], &spring, &Euclidean),
+        potential_energy(&particles[0], &particles[1], &spring));
+}
@@ -0,0 +1,261 @@
+extern crate num;
+use self::num::Float;
+use super::*;
+use std::cell::Cell;
+#[cfg(test)]
+use super::super::integrate::*;
+
+///Particles stored as a structure of arrays, with a separate column for each of their properties.
+///
+///Loops that only read some properties, such as positions, touch much less memory than with a slice of BasicParticle.
+///The particles can be used with the existing pair functions and integrators through handles, and the columns can be
+///advanced all at once with advance.
+#[derive(Clone, Default)]
+pub struct ParticleSoA<V, D> {
+    quanta: Vec<D>,
+    inertia: Vec<D>,
+    positions: Vec<V>,
+    velocities: Vec<V>,
+    forces: Vec<V>,
+}
+
+impl<V, D> ParticleSoA<V, D>
+    where V: Vector<D>, D: Float
+{
+    pub fn new() -> Self {
+        ParticleSoA{
+            quanta: Vec::new(),
+            inertia: Vec::new(),
+            positions: Vec::new(),
+            velocities: Vec::new(),
+            forces: Vec::new(),
+        }
+    }
+
+    ///Add a particle with no force on it and get its index, with the same parameters as BasicParticle::new.
+    pub fn push(&mut self, quanta: D, position: V, velocity: V, inertia: D) -> usize {
+        self.quanta.push(quanta);
+        self.inertia.push(inertia);
+        self.positions.push(position);
+        self.velocities.push(velocity);
+        self.forces.push(V::zero());
+        self.len() - 1
+    }
+
+    ///The amount of particles.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn quanta(&self) -> &[D] {
+        &self.quanta
+    }
+
+    pub fn quanta_mut(&mut self) -> &mut [D] {
+        &mut self.quanta
+    }
+
+    pub fn inertia(&self) -> &[D] {
+        &self.inertia
+    }
+
+    pub fn inertia_mut(&mut self) -> &mut [D] {
+        &mut self.inertia
+    }
+
+    pub fn positions(&self) -> &[V] {
+        &self.positions
+    }
+
+    pub fn positions_mut(&mut self) -> &mut [V] {
+        &mut self.positions
+    }
+
+    pub fn velocities(&self) -> &[V] {
+        &self.velocities
+    }
+
+    pub fn velocities_mut(&mut self) -> &mut [V] {
+        &mut self.velocities
+    }
+
+    ///The net force applied to each particle since it was last advanced.
+    pub fn forces(&self) -> &[V] {
+        &self.forces
+    }
+
+    pub fn forces_mut(&mut self) -> &mut [V] {
+        &mut self.forces
+    }
+
+    ///Get a handle to every particle, in order, which can be used wherever a particle can.
+    ///
+    ///The handles mutably borrow the columns, so they must be dropped before the columns are used directly again.
+    ///Every call allocates a new Vec of handles, so a loop that doesn't need the columns between its steps should get
+    ///the handles once before it rather than on every step.
+    pub fn handles(&mut self) -> Vec<ParticleHandle<'_, V, D>> {
+        let quanta = Cell::from_mut(&mut self.quanta[..]).as_slice_of_cells();
+        let inertia = Cell::from_mut(&mut self.inertia[..]).as_slice_of_cells();
+        let positions = Cell::from_mut(&mut self.positions[..]).as_slice_of_cells();
+        let velocities = Cell::from_mut(&mut self.velocities[..]).as_slice_of_cells();
+        let forces = Cell::from_mut(&mut self.forces[..]).as_slice_of_cells();
+        positions.iter().zip(velocities).zip(forces).zip(quanta.iter().zip(inertia))
+            .map(|(((position, velocity), force), (quanta, inertia))| ParticleHandle{
+                quanta: quanta,
+                inertia: inertia,
+                position: position,
+                velocity: velocity,
+                force: force,
+            })
+            .collect()
+    }
+
+    ///Advance every particle forward in time as Particle::advance does, but over whole columns.
+    pub fn advance(&mut self, time: D) {
+        for ((velocity, force), &inertia) in self.velocities.iter_mut().zip(&mut self.forces).zip(&self.inertia) {
+            *velocity = *velocity + *force / inertia * time;
+            *force = V::zero();
+        }
+        for (position, &velocity) in self.positions.iter_mut().zip(&self.velocities) {
+            *position = *position + velocity * time;
+        }
+    }
+}
+
+///A particle in a ParticleSoA, made by ParticleSoA::handles.
+///
+///Every property is read from and written to the columns of the ParticleSoA.
+#[derive(Copy, Clone)]
+pub struct ParticleHandle<'a, V: 'a, D: 'a> {
+    quanta: &'a Cell<D>,
+    inertia: &'a Cell<D>,
+    position: &'a Cell<V>,
+    velocity: &'a Cell<V>,
+    force: &'a Cell<V>,
+}
+
+impl<'a, V, D> Quanta<D> for ParticleHandle<'a, V, D>
+    where V: Vector<D>, D: Float
+{
+    fn quanta(&self) -> D {
+        self.quanta.get()
+    }
+}
+
+impl<'a, V, D> Inertia<D> for ParticleHandle<'a, V, D>
+    where V: Vector<D>, D: Float
+{
+    fn inertia(&self) -> D {
+        self.inertia.get()
+    }
+}
+
+impl<'a, V, D> Position<V> for ParticleHandle<'a, V, D>
+    where V: Vector<D>, D: Float
+{
+    fn position(&self) -> V {
+        self.position.get()
+    }
+}
+
+impl<'a, V, D> Velocity<V> for ParticleHandle<'a, V, D>
+    where V: Vector<D>, D: Float
+{
+    fn velocity(&self) -> V {
+        self.velocity.get()
+    }
+}
+
+impl<'a, V, D> Particle<V, D> for ParticleHandle<'a, V, D>
+    where V: Vector<D>, D: Float
+{
+    fn impulse(&self, vec: &V) {
+        self.force.set(self.force.get() + *vec);
+    }
+
+    fn advance(&mut self, time: D) {
+        let force = self.force.replace(V::zero());
+        self.velocity.set(self.velocity.get() + force / self.inertia.get() * time);
+        self.position.set(self.position.get() + self.velocity.get() * time);
+    }
+}
+
+impl<'a, V, D> IntegrableParticle<V, D> for ParticleHandle<'a, V, D>
+    where V: Vector<D>, D: Float
+{
+    fn take_force(&mut self) -> V {
+        self.force.replace(V::zero())
+    }
+
+    fn set_position(&mut self, position: V) {
+        self.position.set(position);
+    }
+
+    fn set_velocity(&mut self, velocity: V) {
+        self.velocity.set(velocity);
+    }
+}
+
+impl<'a, V, D> PhysicsParticle<V, D> for ParticleHandle<'a, V, D>
+    where V: Vector<D>, D: Float
+{
+}
+
+#[cfg(test)]
+type SoATestVector = Cartesian2<f64>;
+
+#[cfg(test)]
+fn soa_particles() -> (ParticleSoA<SoATestVector, f64>, Vec<BasicParticle<SoATestVector, f64>>) {
+    let mut soa = ParticleSoA::new();
+    let basic = scattered_particles(20, 0.0, 4.0, |i| 1.0 + i * 0.1,
+        |i| Cartesian2::new((i * 0.271).fract() - 0.5, 0.0));
+    for particle in &basic {
+        soa.push(particle.quanta, particle.position, particle.velocity, particle.inertia);
+    }
+    (soa, basic)
+}
+
+#[test]
+fn soa_advance_test() {
+    let (mut soa, mut basic) = soa_particles();
+    for _ in 0..10 {
+        for_each_pair(&soa.handles(), |a, b| gravitate_radius_squared(a, b, 0.1, 1.0));
+        for_each_pair(&basic, |a, b| gravitate_radius_squared(a, b, 0.1, 1.0));
+        soa.advance(0.01);
+        for particle in &mut basic {
+            particle.advance(0.01);
+        }
+    }
+    for (i, particle) in basic.iter().enumerate() {
+        assert_eq!((soa.positions()[i].x, soa.positions()[i].y), (particle.position.x, particle.position.y));
+        assert_eq!(soa.velocities()[i].x, particle.velocity.x);
+    }
+    assert!(soa.forces().iter().all(|force| force.x == 0.0 && force.y == 0.0));
+}
+
+#[test]
+fn soa_integrator_test() {
+    let (mut soa, mut basic) = soa_particles();
+    let mut integrator = VelocityVerlet::new();
+    let mut reference = VelocityVerlet::new();
+    for _ in 0..10 {
+        integrator.step(&mut soa.handles(), 0.01, |particles| for_each_pair(particles, |a, b| hooke(a, b, 1.0)));
+        reference.step(&mut basic, 0.01, |particles| for_each_pair(particles, |a, b| hooke(a, b, 1.0)));
+    }
+    for (position, particle) in soa.positions().iter().zip(&basic) {
+        assert_eq!((position.x, position.y), (particle.position.x, particle.position.y));
+    }
+}
+
+#[test]
+fn soa_handle_columns_test() {
+    let (mut soa, _) = soa_particles();
+    soa.quanta_mut()[3] = 5.0;
+    soa.inertia_mut()[3] = 4.0;
+    let handles = soa.handles();
+    assert_eq!((handles[3].quanta(), handles[3].mass(), handles[3].inertia()), (5.0, 5.0, 4.0));
+}
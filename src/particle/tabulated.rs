@@ -0,0 +1,356 @@
+extern crate num;
+use self::num::{Float, NumCast};
+use super::*;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+///The error returned when a table can not be read or is malformed.
+#[derive(Debug)]
+pub enum TableError {
+    ///The file could not be read.
+    Io(io::Error),
+    ///No section of the table has the keyword.
+    MissingSection(String),
+    ///A line of the table is malformed; lines are counted from one.
+    Invalid {
+        line: usize,
+        reason: String,
+    },
+    ///A table needs at least two points with increasing distances.
+    Points,
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TableError::Io(ref error) => write!(f, "could not read table: {}", error),
+            TableError::MissingSection(ref keyword) => write!(f, "table has no section named {}", keyword),
+            TableError::Invalid{line, ref reason} => write!(f, "invalid table on line {}: {}", line, reason),
+            TableError::Points => write!(f, "table needs at least two points with increasing distances"),
+        }
+    }
+}
+
+impl Error for TableError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match *self {
+            TableError::Io(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TableError {
+    fn from(error: io::Error) -> Self {
+        TableError::Io(error)
+    }
+}
+
+///A cubic spline through points with increasing x.
+#[derive(Clone, Debug)]
+struct Spline<D> {
+    x: Vec<D>,
+    y: Vec<D>,
+    ///The second derivative at every point.
+    second: Vec<D>,
+}
+
+impl<D> Spline<D>
+    where D: Float
+{
+    ///Fit a spline with the given slopes at the ends, or with no curvature at an end without a slope.
+    fn new(x: Vec<D>, y: Vec<D>, start: Option<D>, end: Option<D>) -> Self {
+        let n = x.len();
+        let six = <D as NumCast>::from(6.0).unwrap();
+        let two = D::one() + D::one();
+        let h: Vec<D> = x.windows(2).map(|w| w[1] - w[0]).collect();
+        let slope: Vec<D> = (0..n - 1).map(|i| (y[i + 1] - y[i]) / h[i]).collect();
+        //Rows of the tridiagonal system for the second derivatives.
+        let mut lower = vec![D::zero(); n];
+        let mut diagonal = vec![D::one(); n];
+        let mut upper = vec![D::zero(); n];
+        let mut rhs = vec![D::zero(); n];
+        if let Some(start) = start {
+            diagonal[0] = two * h[0];
+            upper[0] = h[0];
+            rhs[0] = six * (slope[0] - start);
+        }
+        for i in 1..n - 1 {
+            lower[i] = h[i - 1];
+            diagonal[i] = two * (h[i - 1] + h[i]);
+            upper[i] = h[i];
+            rhs[i] = six * (slope[i] - slope[i - 1]);
+        }
+        if let Some(end) = end {
+            lower[n - 1] = h[n - 2];
+            diagonal[n - 1] = two * h[n - 2];
+            rhs[n - 1] = six * (end - slope[n - 2]);
+        }
+        //Solve with the Thomas algorithm.
+        for i in 1..n {
+            let factor = lower[i] / diagonal[i - 1];
+            diagonal[i] = diagonal[i] - factor * upper[i - 1];
+            rhs[i] = rhs[i] - factor * rhs[i - 1];
+        }
+        let mut second = vec![D::zero(); n];
+        second[n - 1] = rhs[n - 1] / diagonal[n - 1];
+        for i in (0..n - 1).rev() {
+            second[i] = (rhs[i] - upper[i] * second[i + 1]) / diagonal[i];
+        }
+        Spline{
+            x: x,
+            y: y,
+            second: second,
+        }
+    }
+
+    ///Evaluate the spline, extending the first and last pieces beyond the ends.
+    fn evaluate(&self, x: D) -> D {
+        let n = self.x.len();
+        //Find the piece whose start is the last point not after x.
+        let i = match self.x.binary_search_by(|probe| probe.partial_cmp(&x).unwrap_or(::std::cmp::Ordering::Less)) {
+            Ok(i) => i,
+            Err(i) => i.max(1) - 1,
+        }.min(n - 2);
+        let six = <D as NumCast>::from(6.0).unwrap();
+        let h = self.x[i + 1] - self.x[i];
+        let (a, b) = (self.x[i + 1] - x, x - self.x[i]);
+        (self.second[i] * a * a * a + self.second[i + 1] * b * b * b) / (six * h) +
+            (self.y[i] / h - self.second[i] * h / six) * a + (self.y[i + 1] / h - self.second[i + 1] * h / six) * b
+    }
+}
+
+///A potential interpolated from a table of distances, energies and forces.
+///
+///Energy and force are each interpolated with their own cubic spline, like LAMMPS does. The energy spline has the
+///negative of the tabulated forces as its slopes at the ends, and the force spline has no curvature at its ends
+///unless slopes are given. Beyond the last distance, the energy and force are zero, so the table acts as a cutoff;
+///before the first distance, the first piece of each spline is extended.
+#[derive(Clone, Debug)]
+pub struct Tabulated<D> {
+    energy: Spline<D>,
+    force: Spline<D>,
+}
+
+impl<D> Tabulated<D>
+    where D: Float
+{
+    ///Create a potential from points of distance, energy and force.
+    ///
+    ///There must be at least two points, in order of strictly increasing distance.
+    pub fn new(distances: Vec<D>, energies: Vec<D>, forces: Vec<D>) -> Result<Self, TableError> {
+        Tabulated::with_force_slopes(distances, energies, forces, None)
+    }
+
+    ///Same as new, but with the slopes of the force at the first and last distance, like FPRIME in LAMMPS.
+    pub fn with_force_slopes(distances: Vec<D>, energies: Vec<D>, forces: Vec<D>, slopes: Option<(D, D)>)
+        -> Result<Self, TableError>
+    {
+        if distances.len() < 2 || distances.len() != energies.len() || distances.len() != forces.len() ||
+            distances.windows(2).any(|w| w[1].partial_cmp(&w[0]) != Some(::std::cmp::Ordering::Greater))
+        {
+            return Err(TableError::Points);
+        }
+        let n = distances.len();
+        let energy = Spline::new(distances.clone(), energies, Some(-forces[0]), Some(-forces[n - 1]));
+        let force = Spline::new(distances, forces, slopes.map(|s| s.0), slopes.map(|s| s.1));
+        Ok(Tabulated{
+            energy: energy,
+            force: force,
+        })
+    }
+
+    ///Read a section of a table in the format of LAMMPS pair_style table.
+    ///
+    ///Each section starts with a line holding its keyword, then a line of parameters: N and the amount of points,
+    ///optionally followed by R or RSQ with the first and last distance, which replace the distances of the points
+    ///so that they are evenly spaced in distance or in distance squared, and FPRIME with the slopes of the force at
+    ///the first and last distance. The points follow, one per line, as an index, distance, energy and force. Blank
+    ///lines and anything after a # are ignored. BITMAP tables are not supported.
+    pub fn parse(text: &str, keyword: &str) -> Result<Self, TableError> {
+        //Lines with their numbers, without comments or blank lines.
+        let mut lines = text.lines().enumerate()
+            .map(|(index, line)| (index + 1, line.split('#').next().unwrap_or("").trim()))
+            .filter(|&(_, line)| !line.is_empty());
+        loop {
+            match lines.next() {
+                Some((_, line)) if line.split_whitespace().next() == Some(keyword) => break,
+                Some(_) => {},
+                None => return Err(TableError::MissingSection(keyword.to_string())),
+            }
+        }
+        let (number, parameters) = match lines.next() {
+            Some(line) => line,
+            None => return Err(TableError::MissingSection(keyword.to_string())),
+        };
+        let invalid = |line: usize, reason: &str| TableError::Invalid{
+            line: line,
+            reason: reason.to_string(),
+        };
+        let mut count = None;
+        let mut spacing = None;
+        let mut slopes = None;
+        let mut tokens = parameters.split_whitespace();
+        while let Some(token) = tokens.next() {
+            let mut value = || tokens.next().and_then(|t| t.parse::<f64>().ok())
+                .ok_or_else(|| invalid(number, &format!("expected a number after {}", token)));
+            match token {
+                "N" => count = Some(value()? as usize),
+                "R" => spacing = Some((false, value()?, value()?)),
+                "RSQ" => spacing = Some((true, value()?, value()?)),
+                "FPRIME" => slopes = Some((value()?, value()?)),
+                "BITMAP" => return Err(invalid(number, "BITMAP tables are not supported")),
+                _ => return Err(invalid(number, &format!("unknown parameter {}", token))),
+            }
+        }
+        let count = count.ok_or_else(|| invalid(number, "missing N"))?;
+        let (mut distances, mut energies, mut forces) = (Vec::new(), Vec::new(), Vec::new());
+        for _ in 0..count {
+            let (number, line) = lines.next().ok_or_else(|| invalid(number, "fewer points than N"))?;
+            let values = line.split_whitespace().skip(1).map(|t| t.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>().map_err(|_| invalid(number, "expected numbers"))?;
+            if values.len() < 3 {
+                return Err(invalid(number, "expected an index, distance, energy and force"));
+            }
+            distances.push(values[0]);
+            energies.push(values[1]);
+            forces.push(values[2]);
+        }
+        if let Some((squared, first, last)) = spacing {
+            let steps = (count.max(2) - 1) as f64;
+            for (index, distance) in distances.iter_mut().enumerate() {
+                let fraction = index as f64 / steps;
+                *distance = if squared {
+                    (first * first + (last * last - first * first) * fraction).sqrt()
+                } else {
+                    first + (last - first) * fraction
+                };
+            }
+        }
+        let convert = |values: Vec<f64>| values.into_iter().map(|v| <D as NumCast>::from(v).unwrap()).collect();
+        Tabulated::with_force_slopes(convert(distances), convert(energies), convert(forces),
+            slopes.map(|(first, last)| (<D as NumCast>::from(first).unwrap(), <D as NumCast>::from(last).unwrap())))
+    }
+
+    ///Read a section of a table from a file; see parse for the format.
+    pub fn load<P>(path: P, keyword: &str) -> Result<Self, TableError>
+        where P: AsRef<Path>
+    {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+        Tabulated::parse(&text, keyword)
+    }
+
+    ///The last distance in the table, beyond which there is no interaction.
+    pub fn range(&self) -> D {
+        *self.energy.x.last().unwrap()
+    }
+}
+
+impl<D> PairPotential<D> for Tabulated<D>
+    where D: Float
+{
+    fn energy(&self, distance: D) -> D {
+        if distance > self.range() {
+            D::zero()
+        } else {
+            self.energy.evaluate(distance)
+        }
+    }
+
+    fn force(&self, distance: D) -> D {
+        if distance > self.range() {
+            D::zero()
+        } else {
+            self.force.evaluate(distance)
+        }
+    }
+}
+
+impl<D> ScaledBy for Tabulated<D> {
+    type Amount = ByQuanta;
+}
+
+///Write a Lennard-Jones table in the LAMMPS format with evenly spaced points.
+#[cfg(test)]
+fn lennard_jones_table(keyword: &str, count: usize, first: f64, last: f64) -> String {
+    let lj = LennardJones::new(1.0, 1.0);
+    let mut text = format!("# Lennard-Jones\n\n{}\nN {} R {} {}\n\n", keyword, count, first, last);
+    for index in 0..count {
+        let r = first + (last - first) * index as f64 / (count - 1) as f64;
+        text.push_str(&format!("{} {} {} {}\n", index + 1, r, lj.energy(r), lj.force(r)));
+    }
+    text
+}
+
+#[test]
+fn tabulated_interpolation_test() {
+    let text = format!("{}\n{}", lennard_jones_table("OTHER", 10, 1.0, 2.0), lennard_jones_table("LJ", 400, 0.9, 2.5));
+    let table = Tabulated::<f64>::parse(&text, "LJ").unwrap();
+    let lj = LennardJones::new(1.0, 1.0);
+    for &r in &[0.93, 1.0, 1.12, 1.377, 2.0, 2.499] {
+        assert!((table.energy(r) - lj.energy(r)).abs() < 1e-5, "energy at {}", r);
+        assert!((table.force(r) - lj.force(r)).abs() < 1e-4, "force at {}", r);
+    }
+    assert_eq!(table.range(), 2.5);
+    assert_eq!(table.energy(2.6), 0.0);
+    //It plugs into interact like any other potential.
+    type P = BasicParticle<Cartesian2<f64>, f64>;
+    let mut a = P::default();
+    let mut b = P::new(1.0, Cartesian2::new(1.3, 0.0), Cartesian2::new(0.0, 0.0), 1.0);
+    interact(&a, &b, &table);
+    a.take_force();
+    assert!((b.take_force().x - lj.force(1.3)).abs() < 1e-4);
+}
+
+#[test]
+fn tabulated_rsq_test() {
+    //Distances given in the file are replaced by RSQ spacing.
+    let mut text = String::from("SPRING\nN 50 RSQ 0.5 3.0\n\n");
+    for index in 0..50 {
+        let r = (0.25 + (9.0 - 0.25) * index as f64 / 49.0).sqrt();
+        text.push_str(&format!("{} 0.0 {} {}\n", index + 1, r * r, -2.0 * r));
+    }
+    let table = Tabulated::<f64>::parse(&text, "SPRING").unwrap();
+    for &r in &[0.6, 1.7, 2.9] {
+        assert!((table.energy(r) - r * r).abs() < 1e-4);
+        assert!((table.force(r) + 2.0 * r).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn tabulated_error_test() {
+    match Tabulated::<f64>::parse(&lennard_jones_table("LJ", 5, 1.0, 2.0), "MISSING") {
+        Err(TableError::MissingSection(ref keyword)) if keyword == "MISSING" => {},
+        other => panic!("unexpected {:?}", other),
+    }
+    match Tabulated::<f64>::parse("LJ\nN 3\n\n1 1.0 0.0 0.0\n2 1.5 zero 0.0\n", "LJ") {
+        Err(TableError::Invalid{line: 5, ..}) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+    //Too few points is blamed on the line with N.
+    match Tabulated::<f64>::parse("LJ\nN 3\n\n1 1.0 0.0 0.0\n", "LJ") {
+        Err(TableError::Invalid{line: 2, ..}) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+    match Tabulated::<f64>::new(vec![1.0, 1.0], vec![0.0, 0.0], vec![0.0, 0.0]) {
+        Err(TableError::Points) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+    match Tabulated::<f64>::load("/nonexistent/zoom/table", "LJ") {
+        Err(TableError::Io(_)) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn tabulated_load_test() {
+    let path = ::std::env::temp_dir().join(format!("zoom_tabulated_load_test_{}.table", ::std::process::id()));
+    ::std::fs::write(&path, lennard_jones_table("LJ", 100, 0.9, 2.5)).unwrap();
+    let table = Tabulated::<f64>::load(&path, "LJ").unwrap();
+    ::std::fs::remove_file(&path).unwrap();
+    assert!((table.energy(1.5) - LennardJones::new(1.0, 1.0).energy(1.5)).abs() < 1e-3);
+}
@@ -0,0 +1,286 @@
+extern crate num;
+use self::num::Float;
+use super::super::particle::*;
+use super::super::vector::*;
+
+///Deepest level a node may be split to; particles that still share a node at this depth are merged together.
+const MAX_DEPTH: usize = 64;
+
+struct Node<V, D> {
+    bounds: Box<V>,
+    ///A particle standing in for every particle inside of the node, located at their center of mass, with their total
+    ///Mass as its quanta.
+    aggregate: BasicParticle<V, D>,
+    ///Index of the first child node; the children of a node are stored contiguously in orthant order.
+    children: Option<usize>,
+    ///Amount of particles inside of the node.
+    count: usize,
+}
+
+impl<V, D> Node<V, D>
+    where V: Vector<D>, D: Float
+{
+    fn new(bounds: Box<V>) -> Self {
+        Node{
+            bounds: bounds,
+            aggregate: BasicParticle::new(D::zero(), V::zero(), V::zero(), D::zero()),
+            children: None,
+            count: 0,
+        }
+    }
+}
+
+///A Barnes-Hut tree (a quadtree in 2d and an octree in 3d) that approximates the gravitation of a whole
+///collection of particles in O(n log n) time.
+///
+///Every node of the tree aggregates the Mass of the particles inside of it at their center of mass. When a node
+///is far enough away from a particle, as decided by the opening angle theta, the particle gravitates towards the
+///node as a whole instead of towards each particle inside of it. A theta of zero makes the result exact. Deltas
+///to the nodes are measured with the metric of the tree, so in a periodic box particles gravitate towards the nearest
+///image of each node.
+pub struct BarnesHut<V, D, M = Euclidean> {
+    nodes: Vec<Node<V, D>>,
+    ///Space the particles are in.
+    metric: M,
+    ///The ratio of node width to distance below which a node is treated as a single particle.
+    pub theta: D,
+}
+
+impl<V, D> BarnesHut<V, D>
+    where V: CartesianVector<D>, D: Float
+{
+    ///Build a tree from the current state of the particles.
+    ///
+    ///The tree does not track the particles, so it must be rebuilt after they advance.
+    pub fn new<P>(particles: &[P], theta: D) -> Self
+        where P: PhysicsParticle<V, D>
+    {
+        BarnesHut::with_metric(Euclidean, particles, theta)
+    }
+}
+
+impl<V, D, M> BarnesHut<V, D, M>
+    where V: CartesianVector<D>, D: Float, M: Metric<V>
+{
+    ///Same as new, but deltas are measured with a metric.
+    pub fn with_metric<P>(metric: M, particles: &[P], theta: D) -> Self
+        where P: PhysicsParticle<V, D>
+    {
+        let mut tree = BarnesHut{
+            nodes: vec![Node::new(Box::bounding(particles.iter().map(|p| p.position())))],
+            metric: metric,
+            theta: theta,
+        };
+        for particle in particles {
+            let (position, velocity) = (particle.position(), particle.velocity());
+            tree.insert(0, BasicParticle::new(particle.mass(), position, velocity, particle.inertia()), 0);
+        }
+        tree.aggregate(0);
+        tree
+    }
+
+    ///The metric deltas are measured with.
+    pub fn metric(&self) -> &M {
+        &self.metric
+    }
+
+    ///Total mass of all particles in the tree.
+    pub fn quanta(&self) -> D {
+        self.nodes[0].aggregate.quanta
+    }
+
+    ///Center of mass of all particles in the tree.
+    pub fn center(&self) -> V {
+        self.nodes[0].aggregate.position
+    }
+
+    fn insert(&mut self, index: usize, particle: BasicParticle<V, D>, depth: usize) {
+        self.nodes[index].count += 1;
+        if let Some(first) = self.nodes[index].children {
+            let child = first + self.nodes[index].bounds.orthant(&particle.position);
+            self.insert(child, particle, depth + 1);
+        } else if self.nodes[index].count == 1 {
+            self.nodes[index].aggregate = particle;
+        } else if depth == MAX_DEPTH {
+            merge(&mut self.nodes[index].aggregate, &particle);
+        } else {
+            //Split the leaf and move its particle down into the appropriate child.
+            let first = self.nodes.len();
+            for orthant in 0..V::orthants() {
+                let bounds = self.nodes[index].bounds.orthant_box(orthant);
+                self.nodes.push(Node::new(bounds));
+            }
+            self.nodes[index].children = Some(first);
+            let existing = self.nodes[index].aggregate.clone();
+            let child = first + self.nodes[index].bounds.orthant(&existing.position);
+            self.insert(child, existing, depth + 1);
+            let child = first + self.nodes[index].bounds.orthant(&particle.position);
+            self.insert(child, particle, depth + 1);
+        }
+    }
+
+    ///Compute the aggregates of every branch node from its children.
+    fn aggregate(&mut self, index: usize) {
+        if let Some(first) = self.nodes[index].children {
+            let mut aggregate = BasicParticle::new(D::zero(), V::zero(), V::zero(), D::zero());
+            for child in first..first + V::orthants() {
+                self.aggregate(child);
+                if self.nodes[child].count != 0 {
+                    merge(&mut aggregate, &self.nodes[child].aggregate);
+                }
+            }
+            self.nodes[index].aggregate = aggregate;
+        }
+    }
+
+    ///Call f with every node that must be interacted with at a position to satisfy the opening angle.
+    fn visit<F>(&self, index: usize, position: &V, f: &mut F)
+        where F: FnMut(&BasicParticle<V, D>)
+    {
+        let node = &self.nodes[index];
+        if node.count == 0 {
+            return;
+        }
+        match node.children {
+            Some(first) => {
+                let width = node.bounds.offset.component(0).abs() * (D::one() + D::one());
+                let distance = self.metric.delta(*position, node.aggregate.position).displacement();
+                //A node is never approximated if the position is inside of it since it may contain the particle.
+                if !node.bounds.contains(position) && width < self.theta * distance {
+                    f(&node.aggregate);
+                } else {
+                    for child in first..first + V::orthants() {
+                        self.visit(child, position, f);
+                    }
+                }
+            }
+            None => f(&node.aggregate),
+        }
+    }
+
+    ///Apply gravitation from the whole tree to a single particle like gravitate_to.
+    ///
+    ///A particle that is in the tree does not gravitate towards itself.
+    pub fn gravitate_to<T: ?Sized>(&self, particle: &T, magnitude: D)
+        where T: PhysicsParticle<V, D>
+    {
+        let position = particle.position();
+        let gravity = Gravity::new(magnitude);
+        self.visit(0, &position, &mut |node| {
+            particle.impulse(&gravity.force_on(particle, node, self.metric.delta(position, node.position)));
+        });
+    }
+
+    ///Apply gravitation from the whole tree to every particle.
+    ///
+    ///When called with the particles the tree was built from, this approximates calling gravitate on every pair.
+    pub fn gravitate<P>(&self, particles: &[P], magnitude: D)
+        where P: PhysicsParticle<V, D>
+    {
+        for particle in particles {
+            self.gravitate_to(particle, magnitude);
+        }
+    }
+
+    ///Same as gravitate_to, but the force is softened like gravitate_radius_squared.
+    pub fn gravitate_radius_squared_to<T: ?Sized>(&self, particle: &T, radius_squared: D, magnitude: D)
+        where T: PhysicsParticle<V, D>
+    {
+        let position = particle.position();
+        let softened = SoftenedGravity::new(radius_squared, magnitude);
+        self.visit(0, &position, &mut |node| {
+            particle.impulse(&softened.force_on(particle, node, self.metric.delta(position, node.position)));
+        });
+    }
+
+    ///Same as gravitate, but the force is softened like gravitate_radius_squared.
+    pub fn gravitate_radius_squared<P>(&self, particles: &[P], radius_squared: D, magnitude: D)
+        where P: PhysicsParticle<V, D>
+    {
+        for particle in particles {
+            self.gravitate_radius_squared_to(particle, radius_squared, magnitude);
+        }
+    }
+}
+
+///Merge a particle into an aggregate, keeping the aggregate at the center of quanta.
+fn merge<V, D>(aggregate: &mut BasicParticle<V, D>, particle: &BasicParticle<V, D>)
+    where V: Vector<D>, D: Float
+{
+    let quanta = aggregate.quanta + particle.quanta;
+    if quanta.is_normal() {
+        aggregate.position = (aggregate.position * aggregate.quanta + particle.position * particle.quanta) / quanta;
+        aggregate.velocity = (aggregate.velocity * aggregate.quanta + particle.velocity * particle.quanta) / quanta;
+    }
+    aggregate.quanta = quanta;
+    aggregate.inertia = aggregate.inertia + particle.inertia;
+}
+
+///Scattered particles with varied quanta.
+#[cfg(test)]
+fn test_particles<V>(n: usize) -> Vec<BasicParticle<V, f64>>
+    where V: CartesianVector<f64>
+{
+    scattered_particles(n, 0.0, 10.0, |i| 1.0 + (i * 0.377).fract(), |_| V::zero())
+}
+
+#[test]
+fn barnes_hut_exact_test() {
+    type V = Cartesian3<f64>;
+    let mut direct = test_particles::<V>(64);
+    let mut approx = direct.clone();
+    for i in 0..direct.len() {
+        for j in i + 1..direct.len() {
+            gravitate(&direct[i], &direct[j], 1.0);
+        }
+    }
+    BarnesHut::new(&approx, 0.0).gravitate(&approx, 1.0);
+    for (a, b) in direct.iter_mut().zip(approx.iter_mut()) {
+        a.advance(1.0);
+        b.advance(1.0);
+        assert!((a.velocity - b.velocity).displacement() < 1e-9 * a.velocity.displacement());
+    }
+}
+
+#[test]
+fn barnes_hut_approximate_test() {
+    type V = Cartesian2<f64>;
+    let mut direct = test_particles::<V>(256);
+    let mut approx = direct.clone();
+    for i in 0..direct.len() {
+        for j in i + 1..direct.len() {
+            gravitate(&direct[i], &direct[j], 1.0);
+        }
+    }
+    let tree = BarnesHut::new(&approx, 0.5);
+    assert!((tree.quanta() - direct.iter().map(|p| p.quanta).fold(0.0, |a, b| a + b)).abs() < 1e-9);
+    tree.gravitate(&approx, 1.0);
+    //Individual forces may nearly cancel, so the error is measured over the whole collection.
+    let (mut error, mut total) = (0.0, 0.0);
+    for (a, b) in direct.iter_mut().zip(approx.iter_mut()) {
+        a.advance(1.0);
+        b.advance(1.0);
+        error += (a.velocity - b.velocity).displacement_squared();
+        total += a.velocity.displacement_squared();
+    }
+    assert!((error / total).sqrt() < 0.03, "relative error {}", (error / total).sqrt());
+}
+
+#[test]
+fn barnes_hut_metric_test() {
+    type V = Cartesian2<f64>;
+    let bounds = Box::new(V::new(5.0, 5.0), V::new(5.0, 5.0));
+    let mut direct = test_particles::<V>(64);
+    let mut approx = direct.clone();
+    for i in 0..direct.len() {
+        for j in i + 1..direct.len() {
+            gravitate_in(&direct[i], &direct[j], 1.0, &bounds);
+        }
+    }
+    BarnesHut::with_metric(&bounds, &approx, 0.0).gravitate(&approx, 1.0);
+    for (a, b) in direct.iter_mut().zip(approx.iter_mut()) {
+        a.advance(1.0);
+        b.advance(1.0);
+        assert!((a.velocity - b.velocity).displacement() < 1e-9 * a.velocity.displacement());
+    }
+}
@@ -0,0 +1,6 @@
+//!Contains trees that partition particles in space to accelerate their interactions
+
+pub mod barnes_hut;
+pub use self::barnes_hut::*;
+pub mod spatial_tree;
+pub use self::spatial_tree::*;
@@ -0,0 +1,498 @@
+extern crate num;
+use self::num::Float;
+use super::super::particle::*;
+use super::super::vector::*;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+///Deepest level a node may be split to; leaves at this depth hold any amount of items.
+const MAX_DEPTH: usize = 32;
+
+///Amount of items a leaf holds before it is split by default.
+const LEAF_CAPACITY: usize = 8;
+
+struct Entry<V, T> {
+    item: T,
+    ///Position of the item when it was last placed in the tree.
+    position: V,
+    ///Leaf node that the item is stored in.
+    node: usize,
+}
+
+struct TreeNode<V> {
+    bounds: Box<V>,
+    parent: Option<usize>,
+    ///Index of the first child node; the children of a node are stored contiguously in orthant order.
+    children: Option<usize>,
+    ///Handles of the items stored in a leaf.
+    items: Vec<usize>,
+    ///Amount of items inside of the node and all of its descendants.
+    count: usize,
+}
+
+impl<V> TreeNode<V> {
+    fn new(bounds: Box<V>, parent: Option<usize>) -> Self {
+        TreeNode{
+            bounds: bounds,
+            parent: parent,
+            children: None,
+            items: Vec::new(),
+            count: 0,
+        }
+    }
+}
+
+///A quadtree in 2d or an octree in 3d that indexes anything with a Position for spatial queries.
+///
+///Each item inserted is given a handle which stays valid until the item is removed. Items are located by the
+///position they had when they were last inserted or updated, so after an item moves, update must be called with its
+///handle (or update_all for every item) before queries will see the new position. The distances that within_radius
+///and nearest search by are measured with the metric of the tree, so with a periodic box they find items across its
+///walls, while within_box is not affected by the metric.
+pub struct SpatialTree<V, D, T, M = Euclidean> {
+    entries: Vec<Option<Entry<V, T>>>,
+    free_entries: Vec<usize>,
+    nodes: Vec<TreeNode<V>>,
+    free_nodes: Vec<usize>,
+    leaf_capacity: usize,
+    ///Space the items are in.
+    metric: M,
+    _phantom: PhantomData<D>,
+}
+
+impl<V, D, T> SpatialTree<V, D, T>
+    where V: CartesianVector<D>, D: Float, T: Position<V>
+{
+    ///Create an empty tree.
+    pub fn new() -> Self {
+        SpatialTree::with_leaf_capacity(LEAF_CAPACITY)
+    }
+
+    ///Create an empty tree whose leaves hold up to leaf_capacity items before they are split.
+    pub fn with_leaf_capacity(leaf_capacity: usize) -> Self {
+        SpatialTree::with_metric(Euclidean, leaf_capacity)
+    }
+}
+
+impl<V, D, T, M> SpatialTree<V, D, T, M>
+    where V: CartesianVector<D>, D: Float, T: Position<V>, M: Metric<V>
+{
+    ///Same as with_leaf_capacity, but distances are measured with a metric.
+    pub fn with_metric(metric: M, leaf_capacity: usize) -> Self {
+        SpatialTree{
+            entries: Vec::new(),
+            free_entries: Vec::new(),
+            nodes: vec![TreeNode::new(Box::new(V::zero(), V::zero()), None)],
+            free_nodes: Vec::new(),
+            leaf_capacity: if leaf_capacity == 0 { 1 } else { leaf_capacity },
+            metric: metric,
+            _phantom: PhantomData,
+        }
+    }
+
+    ///The metric distances are measured with.
+    pub fn metric(&self) -> &M {
+        &self.metric
+    }
+
+    ///Amount of items in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes[0].count
+    }
+
+    ///Check if the tree has no items.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Insert an item and get the handle that refers to it.
+    pub fn insert(&mut self, item: T) -> usize {
+        let entry = Entry{
+            position: item.position(),
+            item: item,
+            node: 0,
+        };
+        let handle = match self.free_entries.pop() {
+            Some(handle) => {
+                self.entries[handle] = Some(entry);
+                handle
+            }
+            None => {
+                self.entries.push(Some(entry));
+                self.entries.len() - 1
+            }
+        };
+        self.attach(handle);
+        handle
+    }
+
+    ///Remove an item from the tree, returning it if the handle was valid.
+    pub fn remove(&mut self, handle: usize) -> Option<T> {
+        self.get(handle)?;
+        self.detach(handle);
+        self.free_entries.push(handle);
+        self.entries[handle].take().map(|entry| entry.item)
+    }
+
+    ///Get an item from its handle.
+    pub fn get(&self, handle: usize) -> Option<&T> {
+        self.entries.get(handle).and_then(|entry| entry.as_ref()).map(|entry| &entry.item)
+    }
+
+    ///Get an item mutably from its handle; call update with the handle if the item is moved.
+    pub fn get_mut(&mut self, handle: usize) -> Option<&mut T> {
+        self.entries.get_mut(handle).and_then(|entry| entry.as_mut()).map(|entry| &mut entry.item)
+    }
+
+    ///Iterate over the handles and items in the tree.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item=(usize, &'a T)> + 'a {
+        self.entries.iter().enumerate().filter_map(|(handle, entry)| entry.as_ref().map(|entry| (handle, &entry.item)))
+    }
+
+    ///Move an item to its current position in the tree after it has moved.
+    ///
+    ///Returns false if the handle was not valid.
+    pub fn update(&mut self, handle: usize) -> bool {
+        let (position, node) = match self.entries.get_mut(handle).and_then(|entry| entry.as_mut()) {
+            Some(entry) => {
+                entry.position = entry.item.position();
+                (entry.position, entry.node)
+            }
+            None => return false,
+        };
+        if !self.nodes[node].bounds.contains(&position) {
+            self.detach(handle);
+            self.attach(handle);
+        }
+        true
+    }
+
+    ///Move every item to its current position, rebuilding the tree.
+    ///
+    ///This is more efficient than calling update on every item when most of them have moved.
+    pub fn update_all(&mut self) {
+        for entry in self.entries.iter_mut().filter_map(|entry| entry.as_mut()) {
+            entry.position = entry.item.position();
+        }
+        self.rebuild();
+    }
+
+    ///Find the handles of all items within radius of a position.
+    pub fn within_radius(&self, center: &V, radius: D) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.visit_radius(0, center, radius * radius, &mut found);
+        found
+    }
+
+    ///Find the handles of all items inside of a box, including its boundary.
+    pub fn within_box(&self, bounds: &Box<V>) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.visit_box(0, bounds, &mut found);
+        found
+    }
+
+    ///Find the handles of the k items nearest to a position, ordered from nearest to farthest.
+    pub fn nearest(&self, center: &V, k: usize) -> Vec<usize> {
+        let mut nearest = Vec::with_capacity(k + 1);
+        if k != 0 {
+            self.visit_nearest(0, center, k, &mut nearest);
+        }
+        nearest.into_iter().map(|(_, handle)| handle).collect()
+    }
+
+    fn position(&self, handle: usize) -> V {
+        self.entries[handle].as_ref().unwrap().position
+    }
+
+    fn distance_squared(&self, handle: usize, center: &V) -> D {
+        self.metric.delta(*center, self.position(handle)).displacement_squared()
+    }
+
+    ///Compute the squared distance from a position to the nearest point of a box.
+    ///
+    ///The delta to the middle of the box is measured with the metric, which keeps this exact across the walls of a
+    ///periodic box.
+    fn distance_squared_to_box(&self, bounds: &Box<V>, pos: &V) -> D {
+        let delta = self.metric.delta(bounds.origin, *pos);
+        (0..V::dimensions()).fold(D::zero(), |sum, axis| {
+            let outside = delta.component(axis).abs() - bounds.offset.component(axis).abs();
+            if outside > D::zero() {
+                sum + outside * outside
+            } else {
+                sum
+            }
+        })
+    }
+
+    ///Place an entry that is not in any node into the tree, growing the tree if it lies outside of it.
+    fn attach(&mut self, handle: usize) {
+        let position = self.position(handle);
+        if self.nodes[0].bounds.contains(&position) {
+            self.insert_into(0, handle, 0);
+        } else {
+            self.rebuild();
+        }
+    }
+
+    ///Remove an entry from its node, collapsing any branches that no longer need to be split.
+    fn detach(&mut self, handle: usize) {
+        let leaf = self.entries[handle].as_ref().unwrap().node;
+        let index = self.nodes[leaf].items.iter().position(|&h| h == handle).unwrap();
+        self.nodes[leaf].items.swap_remove(index);
+        let mut collapse = None;
+        let mut node = Some(leaf);
+        while let Some(index) = node {
+            self.nodes[index].count -= 1;
+            if self.nodes[index].children.is_some() && self.nodes[index].count <= self.leaf_capacity {
+                collapse = Some(index);
+            }
+            node = self.nodes[index].parent;
+        }
+        if let Some(index) = collapse {
+            let mut items = Vec::new();
+            self.gather(index, &mut items);
+            for &handle in &items {
+                self.entries[handle].as_mut().unwrap().node = index;
+            }
+            self.nodes[index].items = items;
+        }
+    }
+
+    ///Move all the items below a node into items and free all of its descendants.
+    fn gather(&mut self, index: usize, items: &mut Vec<usize>) {
+        items.append(&mut self.nodes[index].items);
+        if let Some(first) = self.nodes[index].children.take() {
+            for child in first..first + V::orthants() {
+                self.gather(child, items);
+            }
+            self.free_nodes.push(first);
+        }
+    }
+
+    ///Reconstruct the tree around all of the entries with room for them to move.
+    fn rebuild(&mut self) {
+        let bounds = {
+            let entries = self.entries.iter().filter_map(|entry| entry.as_ref());
+            let bounds = Box::bounding(entries.map(|entry| entry.position));
+            Box::new(bounds.origin, bounds.offset * (D::one() + D::one()))
+        };
+        self.nodes.clear();
+        self.free_nodes.clear();
+        self.nodes.push(TreeNode::new(bounds, None));
+        for handle in 0..self.entries.len() {
+            if self.entries[handle].is_some() {
+                self.insert_into(0, handle, 0);
+            }
+        }
+    }
+
+    fn insert_into(&mut self, index: usize, handle: usize, depth: usize) {
+        self.nodes[index].count += 1;
+        if let Some(first) = self.nodes[index].children {
+            let child = first + self.nodes[index].bounds.orthant(&self.position(handle));
+            self.insert_into(child, handle, depth + 1);
+            return;
+        }
+        self.nodes[index].items.push(handle);
+        self.entries[handle].as_mut().unwrap().node = index;
+        if self.nodes[index].items.len() > self.leaf_capacity && depth < MAX_DEPTH {
+            //Split the leaf and move its items down into the appropriate children.
+            let first = self.allocate_children(index);
+            self.nodes[index].children = Some(first);
+            let items: Vec<usize> = self.nodes[index].items.drain(..).collect();
+            for handle in items {
+                let child = first + self.nodes[index].bounds.orthant(&self.position(handle));
+                self.insert_into(child, handle, depth + 1);
+            }
+        }
+    }
+
+    fn allocate_children(&mut self, parent: usize) -> usize {
+        let first = match self.free_nodes.pop() {
+            Some(first) => first,
+            None => {
+                let first = self.nodes.len();
+                for _ in 0..V::orthants() {
+                    self.nodes.push(TreeNode::new(Box::new(V::zero(), V::zero()), None));
+                }
+                first
+            }
+        };
+        for orthant in 0..V::orthants() {
+            self.nodes[first + orthant] = TreeNode::new(self.nodes[parent].bounds.orthant_box(orthant), Some(parent));
+        }
+        first
+    }
+
+    fn visit_radius(&self, index: usize, center: &V, radius_squared: D, found: &mut Vec<usize>) {
+        let node = &self.nodes[index];
+        if node.count == 0 || self.distance_squared_to_box(&node.bounds, center) > radius_squared {
+            return;
+        }
+        match node.children {
+            Some(first) => {
+                for child in first..first + V::orthants() {
+                    self.visit_radius(child, center, radius_squared, found);
+                }
+            }
+            None => {
+                found.extend(node.items.iter().cloned()
+                    .filter(|&handle| self.distance_squared(handle, center) <= radius_squared));
+            }
+        }
+    }
+
+    fn visit_box(&self, index: usize, bounds: &Box<V>, found: &mut Vec<usize>) {
+        let node = &self.nodes[index];
+        let overlaps = (0..V::dimensions()).all(|axis| {
+            (node.bounds.origin.component(axis) - bounds.origin.component(axis)).abs() <=
+                node.bounds.offset.component(axis).abs() + bounds.offset.component(axis).abs()
+        });
+        if node.count == 0 || !overlaps {
+            return;
+        }
+        match node.children {
+            Some(first) => {
+                for child in first..first + V::orthants() {
+                    self.visit_box(child, bounds, found);
+                }
+            }
+            None => {
+                found.extend(node.items.iter().cloned().filter(|&handle| bounds.contains(&self.position(handle))));
+            }
+        }
+    }
+
+    ///Keep the k nearest items in nearest, which is sorted by squared distance.
+    fn visit_nearest(&self, index: usize, center: &V, k: usize, nearest: &mut Vec<(D, usize)>) {
+        let node = &self.nodes[index];
+        if node.count == 0 {
+            return;
+        }
+        if nearest.len() == k && farther(self.distance_squared_to_box(&node.bounds, center), nearest[k - 1].0) {
+            return;
+        }
+        match node.children {
+            Some(first) => {
+                //Visit the closest children first so that the farther ones are more likely to be pruned.
+                let mut children: Vec<(D, usize)> = (first..first + V::orthants())
+                    .map(|child| (self.distance_squared_to_box(&self.nodes[child].bounds, center), child))
+                    .collect();
+                children.sort_by(|a, b| compare_distance(a.0, b.0));
+                for (_, child) in children {
+                    self.visit_nearest(child, center, k, nearest);
+                }
+            }
+            None => {
+                for &handle in &node.items {
+                    let distance_squared = self.distance_squared(handle, center);
+                    if nearest.len() < k || farther(nearest[k - 1].0, distance_squared) {
+                        let index = nearest.iter().position(|n| farther(n.0, distance_squared))
+                            .unwrap_or(nearest.len());
+                        nearest.insert(index, (distance_squared, handle));
+                        nearest.truncate(k);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<V, D, T> Default for SpatialTree<V, D, T>
+    where V: CartesianVector<D>, D: Float, T: Position<V>
+{
+    fn default() -> Self {
+        SpatialTree::new()
+    }
+}
+
+///Order distances from nearest to farthest, where NaN is farther than any number.
+fn compare_distance<D>(a: D, b: D) -> Ordering
+    where D: Float
+{
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (false, true) => Ordering::Less,
+        (true, false) => Ordering::Greater,
+        (true, true) => Ordering::Equal,
+    }
+}
+
+fn farther<D>(a: D, b: D) -> bool
+    where D: Float
+{
+    compare_distance(a, b) == Ordering::Greater
+}
+
+#[test]
+fn spatial_tree_query_test() {
+    type P = BasicParticle<Cartesian2<f64>, f64>;
+    let mut tree = SpatialTree::with_leaf_capacity(2);
+    let handles: Vec<usize> = (0..100).map(|i| {
+        let position = Cartesian2::new((i % 10) as f64, (i / 10) as f64);
+        tree.insert(P::new(1.0, position, Cartesian2::new(0.0, 0.0), 1.0))
+    }).collect();
+    assert_eq!(tree.len(), 100);
+
+    let mut found = tree.within_radius(&Cartesian2::new(4.0, 4.0), 1.0);
+    found.sort();
+    assert_eq!(found, vec![handles[34], handles[43], handles[44], handles[45], handles[54]]);
+
+    let found = tree.within_box(&Box::new(Cartesian2::new(1.5, 1.5), Cartesian2::new(1.0, 1.0)));
+    assert_eq!(found.len(), 4);
+
+    let nearest = tree.nearest(&Cartesian2::new(9.2, 8.9), 3);
+    assert_eq!(nearest[0], handles[99]);
+    assert_eq!(nearest.len(), 3);
+    //Every distance is NaN, which is treated as farthest rather than panicking.
+    assert_eq!(tree.nearest(&Cartesian2::new(f64::NAN, 0.0), 3).len(), 3);
+}
+
+#[test]
+fn spatial_tree_move_test() {
+    type P = BasicParticle<Cartesian3<f64>, f64>;
+    let mut tree = SpatialTree::new();
+    let handles: Vec<usize> = (0..50).map(|i| {
+        let i = i as f64;
+        tree.insert(P::new(1.0, Cartesian3::new(i, -i, 0.5 * i), Cartesian3::new(0.0, 0.0, 0.0), 1.0))
+    }).collect();
+
+    //Move a particle far outside of the tree.
+    tree.get_mut(handles[10]).unwrap().position = Cartesian3::new(1000.0, 1000.0, 1000.0);
+    assert!(tree.update(handles[10]));
+    assert_eq!(tree.nearest(&Cartesian3::new(990.0, 990.0, 990.0), 1), vec![handles[10]]);
+
+    assert!(tree.remove(handles[10]).is_some());
+    assert!(tree.remove(handles[10]).is_none());
+    assert_eq!(tree.len(), 49);
+    assert_eq!(tree.nearest(&Cartesian3::new(990.0, 990.0, 990.0), 1), vec![handles[49]]);
+    for &handle in &handles[20..] {
+        tree.remove(handle);
+    }
+    assert_eq!(tree.within_radius(&Cartesian3::new(0.0, 0.0, 0.0), 1000.0).len(), 19);
+}
+
+#[test]
+fn spatial_tree_metric_test() {
+    type V = Cartesian2<f64>;
+    type P = BasicParticle<V, f64>;
+    let bounds = Box::new(V::new(0.0, 0.0), V::new(5.0, 5.0));
+    let mut tree = SpatialTree::with_metric(&bounds, 2);
+    for i in 0..100 {
+        tree.insert(P::new(1.0, V::new((i % 10) as f64 - 4.5, (i / 10) as f64 - 4.5), V::new(0.0, 0.0), 1.0));
+    }
+    //Near a corner, the items in the other three corners are found through the walls.
+    let corner = V::new(4.9, 4.9);
+    let mut found = tree.within_radius(&corner, 1.0);
+    found.sort();
+    assert_eq!(found, vec![0, 9, 90, 99]);
+    let mut nearest = tree.nearest(&corner, 4);
+    nearest.sort();
+    assert_eq!(nearest, found);
+    //The plain tree only finds the item in the same corner.
+    let mut plain = SpatialTree::with_leaf_capacity(2);
+    for i in 0..100 {
+        plain.insert(P::new(1.0, V::new((i % 10) as f64 - 4.5, (i / 10) as f64 - 4.5), V::new(0.0, 0.0), 1.0));
+    }
+    assert_eq!(plain.within_radius(&corner, 1.0), vec![99]);
+}
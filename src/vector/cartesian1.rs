@@ -1,5 +1,5 @@
 extern crate num;
-use super::Vector;
+use super::{Vector, CartesianVector};
 use self::num::{Float, Zero, FromPrimitive};
 use std::ops::{Add, Sub, Neg, Mul, Div};
 
@@ -87,3 +87,23 @@ impl<D> Vector<D> for Cartesian1<D>
         self.x
     }
 }
+
+impl<D> CartesianVector<D> for Cartesian1<D>
+    where D: Float + FromPrimitive
+{
+    fn dimensions() -> usize {
+        1
+    }
+    fn component(&self, axis: usize) -> D {
+        match axis {
+            0 => self.x,
+            _ => panic!("Cartesian1 has no axis {}", axis),
+        }
+    }
+    fn set_component(&mut self, axis: usize, value: D) {
+        match axis {
+            0 => self.x = value,
+            _ => panic!("Cartesian1 has no axis {}", axis),
+        }
+    }
+}
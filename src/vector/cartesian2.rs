@@ -1,5 +1,5 @@
 extern crate num;
-use super::Vector;
+use super::{Vector, CartesianVector};
 use self::num::{Float, Zero, FromPrimitive};
 use std::ops::{Add, Sub, Neg, Mul, Div};
 use std::f64::consts::PI;
@@ -92,3 +92,25 @@ impl<D> Vector<D> for Cartesian2<D>
         self.x * self.x + self.y * self.y
     }
 }
+
+impl<D> CartesianVector<D> for Cartesian2<D>
+    where D: Float + FromPrimitive
+{
+    fn dimensions() -> usize {
+        2
+    }
+    fn component(&self, axis: usize) -> D {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => panic!("Cartesian2 has no axis {}", axis),
+        }
+    }
+    fn set_component(&mut self, axis: usize, value: D) {
+        match axis {
+            0 => self.x = value,
+            1 => self.y = value,
+            _ => panic!("Cartesian2 has no axis {}", axis),
+        }
+    }
+}
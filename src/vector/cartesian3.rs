@@ -1,5 +1,5 @@
 extern crate num;
-use super::{Vector, CrossVector};
+use super::{Vector, CrossVector, CartesianVector};
 use self::num::{Float, Zero, FromPrimitive};
 use std::ops::{Add, Sub, Neg, Mul, Div};
 use std::f64::consts::PI;
@@ -94,6 +94,30 @@ impl<D> Vector<D> for Cartesian3<D>
     }
 }
 
+impl<D> CartesianVector<D> for Cartesian3<D>
+    where D: Float + FromPrimitive
+{
+    fn dimensions() -> usize {
+        3
+    }
+    fn component(&self, axis: usize) -> D {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            2 => self.z,
+            _ => panic!("Cartesian3 has no axis {}", axis),
+        }
+    }
+    fn set_component(&mut self, axis: usize, value: D) {
+        match axis {
+            0 => self.x = value,
+            1 => self.y = value,
+            2 => self.z = value,
+            _ => panic!("Cartesian3 has no axis {}", axis),
+        }
+    }
+}
+
 impl<D> CrossVector for Cartesian3<D>
     where D: Float
 {
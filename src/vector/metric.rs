@@ -0,0 +1,137 @@
+use super::*;
+use std::ops::Sub;
+
+///The geometry of the space that particles are in, which decides the displacement between two positions.
+///
+///This lets periodicity be configured once, such as on a neighbour list, rather than passing a comp_delta closure to
+///every call.
+pub trait Metric<V> {
+    ///The displacement from one position to another.
+    fn delta(&self, from: V, to: V) -> V;
+
+    ///Bring a position back inside of the space if the space wraps.
+    fn wrap(&self, position: V) -> V {
+        position
+    }
+
+    ///The periodic box that the space repeats in, if any.
+    ///
+    ///Spatial indexes use this to find pairs across the boundary, so a metric that wraps must give its box.
+    fn bounds(&self) -> Option<&Box<V>> {
+        None
+    }
+}
+
+///Ordinary space, where the displacement is the difference of the positions.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Euclidean;
+
+impl<V> Metric<V> for Euclidean
+    where V: Sub<V, Output=V>
+{
+    fn delta(&self, from: V, to: V) -> V {
+        to - from
+    }
+}
+
+///A periodic box is a space where the displacement is the shortest one through any wall of the box.
+impl<V> Metric<V> for Box<V>
+    where V: Sub<V, Output=V>, Box<V>: Toroid<V>
+{
+    fn delta(&self, from: V, to: V) -> V {
+        self.wrap_delta(to - from)
+    }
+
+    fn wrap(&self, position: V) -> V {
+        self.wrap_position(position)
+    }
+
+    fn bounds(&self) -> Option<&Box<V>> {
+        Some(self)
+    }
+}
+
+///A metric defined by a closure that computes the displacement between two positions.
+///
+///Spatial indexes only find pairs across the walls of a periodic box that the metric gives them, so a closure that
+///wraps displacements through the walls of a box must be created with periodic rather than new.
+#[derive(Clone)]
+pub struct FnMetric<F, V> {
+    delta: F,
+    bounds: Option<Box<V>>,
+}
+
+impl<F, V> FnMetric<F, V> {
+    ///Create a metric from a closure for a space that does not wrap.
+    pub fn new(delta: F) -> Self {
+        FnMetric{
+            delta: delta,
+            bounds: None,
+        }
+    }
+
+    ///Create a metric from a closure for a space that wraps through the walls of a periodic box.
+    pub fn periodic(delta: F, bounds: Box<V>) -> Self {
+        FnMetric{
+            delta: delta,
+            bounds: Some(bounds),
+        }
+    }
+}
+
+impl<V, F> Metric<V> for FnMetric<F, V>
+    where F: Fn(V, V) -> V, Box<V>: Toroid<V>
+{
+    fn delta(&self, from: V, to: V) -> V {
+        (self.delta)(from, to)
+    }
+
+    fn wrap(&self, position: V) -> V {
+        match self.bounds {
+            Some(ref bounds) => bounds.wrap_position(position),
+            None => position,
+        }
+    }
+
+    fn bounds(&self) -> Option<&Box<V>> {
+        self.bounds.as_ref()
+    }
+}
+
+impl<V, M: ?Sized> Metric<V> for &M
+    where M: Metric<V>
+{
+    fn delta(&self, from: V, to: V) -> V {
+        (**self).delta(from, to)
+    }
+
+    fn wrap(&self, position: V) -> V {
+        (**self).wrap(position)
+    }
+
+    fn bounds(&self) -> Option<&Box<V>> {
+        (**self).bounds()
+    }
+}
+
+#[test]
+fn metric_test() {
+    let a = Cartesian2::new(-1.5, 0.5);
+    let b = Cartesian2::new(1.5, 0.25);
+    let euclidean = Euclidean.delta(a, b);
+    assert_eq!((euclidean.x, euclidean.y), (3.0, -0.25));
+    let bounds = Box::new(Cartesian2::new(0.0, 0.0), Cartesian2::new(2.0, 2.0));
+    let wrapped = bounds.delta(a, b);
+    assert_eq!((wrapped.x, wrapped.y), (-1.0, -0.25));
+    assert!(Metric::<Cartesian2<f64>>::bounds(&bounds).is_some());
+    let position = Metric::wrap(&bounds, Cartesian2::new(2.5, 0.0));
+    assert_eq!(position.x, -1.5);
+    let custom = FnMetric::new(|from: Cartesian2<f64>, to: Cartesian2<f64>| (to - from) * 2.0);
+    assert_eq!(custom.delta(a, b).x, 6.0);
+    assert_eq!(Metric::delta(&&custom, a, b).x, 6.0);
+    assert!(custom.bounds().is_none());
+    let periodic = FnMetric::periodic(|from, to| bounds.wrap_delta(to - from), bounds.clone());
+    assert_eq!(periodic.delta(a, b).x, -1.0);
+    assert_eq!(periodic.wrap(Cartesian2::new(2.5, 0.0)).x, -1.5);
+    assert!(periodic.bounds().is_some());
+}
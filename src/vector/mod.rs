@@ -4,10 +4,12 @@ pub mod cartesian1;
 pub mod cartesian2;
 pub mod cartesian3;
 pub mod space;
+pub mod metric;
 pub use self::cartesian1::*;
 pub use self::cartesian2::*;
 pub use self::cartesian3::*;
 pub use self::space::*;
+pub use self::metric::*;
 
 extern crate num;
 extern crate nalgebra as na;
@@ -59,6 +61,27 @@ pub trait CrossVector {
     fn cross(lhs: &Self, rhs: &Self) -> Self;
 }
 
+///CartesianVector is a Vector whose components along each axis can be individually accessed.
+///
+///This permits space to be divided along its axes, which is needed for quadtrees and octrees.
+pub trait CartesianVector<D>: Vector<D>
+    where D: Float
+{
+    ///Returns the amount of axes in the dimensional system of this vector
+    fn dimensions() -> usize;
+
+    ///Returns the component of the vector along an axis
+    fn component(&self, axis: usize) -> D;
+
+    ///Sets the component of the vector along an axis
+    fn set_component(&mut self, axis: usize, value: D);
+
+    ///Returns the amount of orthants (quadrants in 2d, octants in 3d) that space is divided into around a point
+    fn orthants() -> usize {
+        1 << Self::dimensions()
+    }
+}
+
 #[test]
 fn cross_vector() {
     let a = Cartesian3::new(0.3, 0.5, 1.0);
@@ -82,6 +105,26 @@ impl<D> Vector<D> for na::Vector1<D>
     }
 }
 
+impl<D> CartesianVector<D> for na::Vector1<D>
+    where D: Float + FromPrimitive
+{
+    fn dimensions() -> usize {
+        1
+    }
+    fn component(&self, axis: usize) -> D {
+        match axis {
+            0 => self.x,
+            _ => panic!("Vector1 has no axis {}", axis),
+        }
+    }
+    fn set_component(&mut self, axis: usize, value: D) {
+        match axis {
+            0 => self.x = value,
+            _ => panic!("Vector1 has no axis {}", axis),
+        }
+    }
+}
+
 impl<D> Vector<D> for na::Vector2<D>
     where D: Float + FromPrimitive
 {
@@ -102,6 +145,28 @@ impl<D> Vector<D> for na::Vector2<D>
     }
 }
 
+impl<D> CartesianVector<D> for na::Vector2<D>
+    where D: Float + FromPrimitive
+{
+    fn dimensions() -> usize {
+        2
+    }
+    fn component(&self, axis: usize) -> D {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => panic!("Vector2 has no axis {}", axis),
+        }
+    }
+    fn set_component(&mut self, axis: usize, value: D) {
+        match axis {
+            0 => self.x = value,
+            1 => self.y = value,
+            _ => panic!("Vector2 has no axis {}", axis),
+        }
+    }
+}
+
 impl<D> Vector<D> for na::Vector3<D>
     where D: Float + FromPrimitive
 {
@@ -122,6 +187,30 @@ impl<D> Vector<D> for na::Vector3<D>
     }
 }
 
+impl<D> CartesianVector<D> for na::Vector3<D>
+    where D: Float + FromPrimitive
+{
+    fn dimensions() -> usize {
+        3
+    }
+    fn component(&self, axis: usize) -> D {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            2 => self.z,
+            _ => panic!("Vector3 has no axis {}", axis),
+        }
+    }
+    fn set_component(&mut self, axis: usize, value: D) {
+        match axis {
+            0 => self.x = value,
+            1 => self.y = value,
+            2 => self.z = value,
+            _ => panic!("Vector3 has no axis {}", axis),
+        }
+    }
+}
+
 impl<D> CrossVector for na::Vector3<D>
     where D: Float
 {
@@ -133,3 +222,18 @@ impl<D> CrossVector for na::Vector3<D>
         }
     }
 }
+
+///Scatter n positions deterministically over a cube from low to high along every axis, for tests that need particles
+///spread out without depending on a random number generator.
+#[cfg(test)]
+pub fn scattered<V>(n: usize, low: f64, high: f64) -> Vec<V>
+    where V: CartesianVector<f64>
+{
+    (0..n).map(|i| {
+        let mut position = V::zero();
+        for (axis, &step) in [0.618, 0.414, 0.732].iter().take(V::dimensions()).enumerate() {
+            position.set_component(axis, low + (i as f64 * step).fract() * (high - low));
+        }
+        position
+    }).collect()
+}
@@ -44,6 +44,78 @@ impl<V> Box<V> {
     {
         self.offset.space_box()
     }
+
+    /// Create the smallest box with equal length edges that contains every position
+    ///
+    /// If there are no positions, the box is empty and sits on the origin.
+    pub fn bounding<D, I>(positions: I) -> Self
+        where V: CartesianVector<D>, D: Float, I: IntoIterator<Item=V>
+    {
+        let mut positions = positions.into_iter();
+        let (mut min, mut max) = match positions.next() {
+            Some(pos) => (pos, pos),
+            None => return Box::new(V::zero(), V::zero()),
+        };
+        for pos in positions {
+            for axis in 0..V::dimensions() {
+                let component = pos.component(axis);
+                if component < min.component(axis) {
+                    min.set_component(axis, component);
+                }
+                if component > max.component(axis) {
+                    max.set_component(axis, component);
+                }
+            }
+        }
+        let two = D::one() + D::one();
+        let half = (0..V::dimensions())
+            .map(|axis| (max.component(axis) - min.component(axis)) / two)
+            .fold(D::zero(), D::max);
+        let mut offset = V::zero();
+        for axis in 0..V::dimensions() {
+            offset.set_component(axis, half);
+        }
+        Box::new((min + max) / two, offset)
+    }
+
+    /// Check if a position lies inside of the box or on its boundary
+    pub fn contains<D>(&self, pos: &V) -> bool
+        where V: CartesianVector<D>, D: Float
+    {
+        (0..V::dimensions()).all(|axis| {
+            (pos.component(axis) - self.origin.component(axis)).abs() <= self.offset.component(axis).abs()
+        })
+    }
+
+    /// Get the index of the orthant around the center of the box that a position lies in
+    ///
+    /// Bit n of the index is set when the position is not below the center along axis n.
+    pub fn orthant<D>(&self, pos: &V) -> usize
+        where V: CartesianVector<D>, D: Float
+    {
+        (0..V::dimensions())
+            .filter(|&axis| pos.component(axis) >= self.origin.component(axis))
+            .fold(0, |index, axis| index | 1 << axis)
+    }
+
+    /// Get the box covering one orthant of this box, as indexed by orthant()
+    pub fn orthant_box<D>(&self, orthant: usize) -> Self
+        where V: CartesianVector<D>, D: Float
+    {
+        let two = D::one() + D::one();
+        let mut origin = self.origin;
+        let mut offset = V::zero();
+        for axis in 0..V::dimensions() {
+            let half = self.offset.component(axis).abs() / two;
+            offset.set_component(axis, half);
+            origin.set_component(axis, if orthant & 1 << axis == 0 {
+                origin.component(axis) - half
+            } else {
+                origin.component(axis) + half
+            });
+        }
+        Box::new(origin, offset)
+    }
 }
 
 fn wrap_scalar<D>(pos: D, bound: D) -> D where D: Float + FromPrimitive {
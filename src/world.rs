@@ -0,0 +1,280 @@
+//!Contains a container that owns everything needed to run a simulation
+
+extern crate num;
+use self::num::Float;
+use super::vector::*;
+use super::particle::*;
+use super::integrate::*;
+
+///A force on each particle that doesn't depend on the other particles, such as a uniform field.
+pub type Field<V, P> = ::std::boxed::Box<dyn Fn(&P) -> V>;
+
+///The interactions that are applied to every unique pair of particles, which is an Interaction itself.
+pub type Interactions<V, D, P> = Vec<::std::boxed::Box<dyn Interaction<V, D, P>>>;
+
+///A callback that is run with the particles and the time after every step.
+pub type Hook<D, P> = ::std::boxed::Box<dyn FnMut(&mut [P], D)>;
+
+///A complete simulation made from particles, the interactions between them, external fields and an integrator.
+///
+///Every step, each interaction is applied to every unique pair of particles and each field to every particle, the
+///integrator advances the particles, and then the hooks are run. If there are bounds, deltas between particles are
+///wrapped through the walls of the box and the particles are wrapped back inside of it after each step. The
+///particles may be any IntegrableParticle, and BasicParticle is used unless another type is given.
+///
+///The integrator is reset whenever the world itself changes the particles or the forces between steps, but if the
+///particles or the integrator are changed directly, reset must be called before the next step.
+pub struct World<V, D, P = BasicParticle<V, D>, I = SymplecticEuler> {
+    pub particles: Vec<P>,
+    pub integrator: I,
+    pub bounds: Option<Box<V>>,
+    interactions: Interactions<V, D, P>,
+    fields: Vec<Field<V, P>>,
+    hooks: Vec<Hook<D, P>>,
+    time: D,
+    steps: usize,
+}
+
+impl<V, D, P> World<V, D, P>
+    where V: Vector<D>, D: Float, Box<V>: Toroid<V>, P: IntegrableParticle<V, D>
+{
+    ///Create an empty world that is advanced with SymplecticEuler as Particle::advance would.
+    pub fn new() -> Self {
+        World::with_integrator(SymplecticEuler)
+    }
+}
+
+impl<V, D, P> Default for World<V, D, P>
+    where V: Vector<D>, D: Float, Box<V>: Toroid<V>, P: IntegrableParticle<V, D>
+{
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+impl<V, D, P, I> World<V, D, P, I>
+    where V: Vector<D>, D: Float, Box<V>: Toroid<V>, P: IntegrableParticle<V, D>, I: Integrator<V, D>
+{
+    ///Create an empty world that is advanced with an integrator.
+    pub fn with_integrator(integrator: I) -> Self {
+        World{
+            particles: Vec::new(),
+            integrator: integrator,
+            bounds: None,
+            interactions: Vec::new(),
+            fields: Vec::new(),
+            hooks: Vec::new(),
+            time: D::zero(),
+            steps: 0,
+        }
+    }
+
+    ///Add a particle and get its index.
+    pub fn add_particle(&mut self, particle: P) -> usize {
+        self.particles.push(particle);
+        self.reset();
+        self.particles.len() - 1
+    }
+
+    ///Add an interaction that is applied to every unique pair of particles.
+    pub fn add_interaction<T>(&mut self, interaction: T)
+        where T: Interaction<V, D, P> + 'static
+    {
+        self.interactions.push(::std::boxed::Box::new(interaction));
+        self.reset();
+    }
+
+    ///Add a field that gives the force on each particle.
+    pub fn add_field<F>(&mut self, field: F)
+        where F: Fn(&P) -> V + 'static
+    {
+        self.fields.push(::std::boxed::Box::new(field));
+        self.reset();
+    }
+
+    ///Add a hook that is run with the particles and the time after every step.
+    pub fn add_hook<F>(&mut self, hook: F)
+        where F: FnMut(&mut [P], D) + 'static
+    {
+        self.hooks.push(::std::boxed::Box::new(hook));
+    }
+
+    ///Total time the world has been advanced.
+    pub fn time(&self) -> D {
+        self.time
+    }
+
+    ///Amount of steps that have been taken.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    ///Apply the interactions and fields to the particles without advancing them.
+    pub fn apply_forces(&self) {
+        apply_world_forces(&self.particles, &self.interactions, &self.fields, self.bounds.as_ref());
+    }
+
+    ///Advance the world forward in time by dt.
+    pub fn step(&mut self, dt: D) {
+        {
+            let interactions = &self.interactions;
+            let fields = &self.fields;
+            let bounds = self.bounds.as_ref();
+            self.integrator.step(&mut self.particles, dt,
+                |particles| apply_world_forces(particles, interactions, fields, bounds));
+        }
+        if let Some(ref bounds) = self.bounds {
+            for particle in &mut self.particles {
+                let position = bounds.wrap_position(particle.position());
+                particle.set_position(position);
+            }
+        }
+        self.time = self.time + dt;
+        self.steps += 1;
+        for hook in &mut self.hooks {
+            hook(&mut self.particles, self.time);
+        }
+        if !self.hooks.is_empty() {
+            self.reset();
+        }
+    }
+
+    ///Make the integrator forget anything kept from the last step, as Integrator::reset does.
+    pub fn reset(&mut self) {
+        self.integrator.reset();
+    }
+
+    ///Take n steps of dt.
+    pub fn run(&mut self, n: usize, dt: D) {
+        for _ in 0..n {
+            self.step(dt);
+        }
+    }
+}
+
+fn apply_world_forces<V, D, P>(particles: &[P], interactions: &Interactions<V, D, P>, fields: &[Field<V, P>],
+    bounds: Option<&Box<V>>)
+    where V: Vector<D>, D: Float, Box<V>: Toroid<V>, P: Particle<V, D>
+{
+    if !interactions.is_empty() {
+        for_each_pair(particles, |lhs, rhs| match bounds {
+            Some(bounds) => interactions.apply_in(lhs, rhs, bounds),
+            None => interactions.apply(lhs, rhs),
+        });
+    }
+    for particle in particles {
+        for field in fields {
+            particle.impulse(&field(particle));
+        }
+    }
+}
+
+#[cfg(test)]
+fn world_particles() -> Vec<BasicParticle<Cartesian2<f64>, f64>> {
+    scattered_particles(8, -2.0, 2.0, |i| 1.0 + i * 0.1, |i| Cartesian2::new(0.0, 0.1 * i))
+}
+
+#[test]
+fn world_step_test() {
+    let mut world = World::new();
+    for particle in world_particles() {
+        world.add_particle(particle);
+    }
+    world.add_interaction(SoftenedGravity::new(0.1, 1.0));
+    world.add_interaction(Hooke::new(0.05));
+    world.add_field(|particle: &BasicParticle<Cartesian2<f64>, f64>| Cartesian2::new(0.0, -particle.quanta));
+    world.run(20, 0.01);
+    assert_eq!(world.steps(), 20);
+    assert!((world.time() - 0.2).abs() < 1e-12);
+
+    //The same simulation written as a loop.
+    let mut particles = world_particles();
+    for _ in 0..20 {
+        for_each_pair(&particles, |a, b| {
+            let force = SoftenedGravity::new(0.1, 1.0).force_on(a, b, b.position - a.position) +
+                Hooke::new(0.05).force_on(a, b, b.position - a.position);
+            a.impulse(&force);
+            b.impulse(&-force);
+        });
+        for particle in &particles {
+            particle.impulse(&Cartesian2::new(0.0, -particle.quanta));
+        }
+        for particle in &mut particles {
+            particle.advance(0.01);
+        }
+    }
+    for (a, b) in world.particles.iter().zip(&particles) {
+        assert_eq!((a.position.x, a.position.y), (b.position.x, b.position.y));
+    }
+}
+
+#[test]
+fn world_bounds_hooks_test() {
+    use std::rc::Rc;
+    use std::cell::Cell;
+    let mut world = World::with_integrator(VelocityVerlet::new());
+    world.particles = world_particles();
+    world.bounds = Some(Box::new(Cartesian2::new(0.0, 0.0), Cartesian2::new(2.0, 2.0)));
+    world.add_interaction(LennardJones::new(0.3, 0.1).cutoff(1.0));
+    world.add_field(|_: &BasicParticle<Cartesian2<f64>, f64>| Cartesian2::new(5.0, 0.0));
+    let calls = Rc::new(Cell::new(0));
+    let outside = Rc::new(Cell::new(false));
+    {
+        let calls = calls.clone();
+        let outside = outside.clone();
+        world.add_hook(move |particles, time| {
+            calls.set(calls.get() + 1);
+            assert!((time - 0.01 * calls.get() as f64).abs() < 1e-12);
+            if particles.iter().any(|p| p.position.x.abs() > 2.0 || p.position.y.abs() > 2.0) {
+                outside.set(true);
+            }
+        });
+    }
+    world.run(200, 0.01);
+    assert_eq!(calls.get(), 200);
+    //The field pushes particles through the walls, but they are wrapped back inside.
+    assert!(!outside.get());
+}
+
+#[test]
+fn world_handles_test() {
+    //A world of handles into a ParticleSoA moves the particles the same as a world of BasicParticle.
+    let mut soa = ParticleSoA::new();
+    let mut basic = World::new();
+    for particle in world_particles() {
+        soa.push(particle.quanta, particle.position, particle.velocity, particle.inertia);
+        basic.add_particle(particle);
+    }
+    basic.add_interaction(SoftenedGravity::new(0.1, 1.0));
+    basic.run(20, 0.01);
+    {
+        let mut world = World::with_integrator(SymplecticEuler);
+        world.particles = soa.handles();
+        world.add_interaction(SoftenedGravity::new(0.1, 1.0));
+        world.run(20, 0.01);
+        assert_eq!(world.steps(), 20);
+    }
+    for (position, particle) in soa.positions().iter().zip(&basic.particles) {
+        assert_eq!((position.x, position.y), (particle.position.x, particle.position.y));
+    }
+}
+
+#[test]
+fn world_reset_test() {
+    //Adding an interaction after running gives the same steps as an integrator that never saw the old forces.
+    let mut world = World::with_integrator(VelocityVerlet::new());
+    world.particles = world_particles();
+    world.add_interaction(SoftenedGravity::new(0.1, 1.0));
+    world.run(10, 0.01);
+    let mut fresh = World::with_integrator(VelocityVerlet::new());
+    fresh.particles = world.particles.clone();
+    fresh.add_interaction(SoftenedGravity::new(0.1, 1.0));
+    fresh.add_interaction(Hooke::new(0.05));
+    world.add_interaction(Hooke::new(0.05));
+    world.run(10, 0.01);
+    fresh.run(10, 0.01);
+    for (a, b) in world.particles.iter().zip(&fresh.particles) {
+        assert_eq!((a.position.x, a.position.y), (b.position.x, b.position.y));
+        assert_eq!((a.velocity.x, a.velocity.y), (b.velocity.x, b.velocity.y));
+    }
+}